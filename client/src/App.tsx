@@ -42,8 +42,8 @@ function App() {
     const validMoves = new Set<string>();
 
     // Check all possible destination squares
-    for (let toY = 0; toY < 8; toY++) {
-      for (let toX = 0; toX < 8; toX++) {
+    for (let toY = 0; toY < gameState.height; toY++) {
+      for (let toX = 0; toX < gameState.width; toX++) {
         const dx = toX - fromX;
         const dy = toY - fromY;
 
@@ -217,14 +217,19 @@ function App() {
 
         {/* THE GRID */}
         <div className="border-4 border-amber-900 bg-amber-900 inline-block shadow-2xl">
-          {/* We map 8 rows (y) */}
-          {Array.from({ length: 8 }).map((_, y) => (
-            <div key={y} className="flex" style={{ height: '96px' }}>
-              {/* We map 8 cols (x) */}
-              {Array.from({ length: 8 }).map((_, x) => {
+          {/* We map one row per board rank (y) */}
+          {Array.from({ length: gameState?.height ?? 8 }).map((_, y) => (
+            <div
+              key={y}
+              className="flex"
+              style={{ height: '96px', marginLeft: gameState?.grid === "hex" ? `${y * 48}px` : 0 }}
+            >
+              {/* We map one col per board file (x) */}
+              {Array.from({ length: gameState?.width ?? 8 }).map((_, x) => {
 
                 // Determine piece at this square
                 const piece = gameState?.board[y]?.[x];
+                const terrain = gameState?.terrain?.[y]?.[x];
 
                 // Styling logic
                 const isSelected = selected?.[0] === x && selected?.[1] === y;
@@ -237,6 +242,14 @@ function App() {
                   bgClass = isBlackSquare(x, y)
                     ? "bg-green-600 opacity-90" // Dark green on dark squares
                     : "bg-green-400 opacity-90"; // Light green on light squares
+                } else if (gameState?.visible?.[y]?.[x] === false) {
+                  bgClass = "bg-slate-600"; // fog of war
+                } else if (terrain === "hole") {
+                  bgClass = "bg-gray-950";
+                } else if (terrain === "water") {
+                  bgClass = "bg-sky-500";
+                } else if (terrain === "forest") {
+                  bgClass = "bg-emerald-800";
                 } else {
                   // Classic chess board colors
                   bgClass = isBlackSquare(x, y)
@@ -1,5 +1,5 @@
 import { useState } from "react";
-import { type MovementCap, type PieceRule, createSlideCapability, createLeapCapability, isSlideCapability, isLeapCapability, type SlidePattern } from "../types/rules";
+import { type MovementCap, type PieceRule, createSlideCapability, createLeapCapability, isSlideCapability, isLeapCapability, type SlidePatternShorthand } from "../types/rules";
 import { customIcons } from "../utils/customIcons";
 
 interface RuleBuilderProps {
@@ -14,7 +14,9 @@ export function RuleBuilder({ onPropose, initialRule }: RuleBuilderProps) {
 
   const initialSlideCap = initialRule?.capabilities.find(isSlideCapability);
   const [canSlide, setCanSlide] = useState(!!initialSlideCap);
-  const [slidePattern, setSlidePattern] = useState<"linear" | "diagonal" | "omni">(initialSlideCap?.slide.pattern || "linear");
+  const [slidePattern, setSlidePattern] = useState<SlidePatternShorthand>(
+    typeof initialSlideCap?.slide.pattern === "string" ? initialSlideCap.slide.pattern : "linear"
+  );
   const [slideRange, setSlideRange] = useState(initialSlideCap?.slide.range || 1);
   const [slideForwardOnly, setSlideForwardOnly] = useState(initialSlideCap?.slide.only_forward || false);
   const [slideCanJump, setSlideCanJump] = useState(initialSlideCap?.slide.can_jump || false);
@@ -130,7 +132,7 @@ export function RuleBuilder({ onPropose, initialRule }: RuleBuilderProps) {
                 <label className="block text-sm mb-1">Pattern</label>
                 <select
                   value={slidePattern}
-                  onChange={(e) => setSlidePattern(e.target.value as SlidePattern)}
+                  onChange={(e) => setSlidePattern(e.target.value as SlidePatternShorthand)}
                   className="w-full px-3 py-2 bg-gray-800 border border-gray-700 rounded outline-none"
                 >
                   <option value="linear">Linear (↑↓←→)</option>
@@ -46,7 +46,7 @@ export function isMoveValid(
       }
 
       // Check pattern matching
-      const matchesPattern = checkPatternMatch(dx, dy, cap.slide.pattern);
+      const matchesPattern = checkPatternMatch(dx, dy, cap.slide.pattern, forwardY);
       if (!matchesPattern) {
         continue;
       }
@@ -55,9 +55,10 @@ export function isMoveValid(
       // Note: can_jump and path checking happen in the actual game state
       return true;
     } else if (isLeapCapability(cap)) {
-      // Check if (dx, dy) exists in the possibilities list
+      // Check if (dx, dy) exists in the possibilities list, offsets follow the owner's forward
+      const fy = cap.leap.absolute ? 1 : forwardY;
       const match = cap.leap.possibilities.some(
-        ([ox, oy]) => ox === dx && oy === dy
+        ([ox, oy]) => ox === dx && oy * fy === dy
       );
       if (match) return true;
     }
@@ -69,7 +70,21 @@ export function isMoveValid(
 /**
  * Check if movement matches the given pattern
  */
-function checkPatternMatch(dx: number, dy: number, pattern: SlidePattern): boolean {
+function checkPatternMatch(
+  dx: number,
+  dy: number,
+  pattern: SlidePattern,
+  forwardY: number
+): boolean {
+  if (typeof pattern === "object") {
+    // Custom directions: (dx, dy) must be a whole number of steps along one of them
+    return pattern.custom.some(({ step, range = 0 }) => {
+      const [sx, sy] = [step[0], step[1] * forwardY];
+      if (sx === 0 && sy === 0) return false;
+      const k = sx !== 0 ? dx / sx : dy / sy;
+      return Number.isInteger(k) && k > 0 && sx * k === dx && sy * k === dy && (range === 0 || k <= range);
+    });
+  }
   switch (pattern) {
     case "linear":
       // Orthogonal: Either horizontal or vertical, not both
@@ -117,7 +132,8 @@ export function generateValidMoves(
  */
 export function describeCapability(cap: MovementCap): string {
   if (isSlideCapability(cap)) {
-    let desc = `Slide ${cap.slide.pattern}`;
+    const pattern = typeof cap.slide.pattern === "string" ? cap.slide.pattern : "custom";
+    let desc = `Slide ${pattern}`;
     if (cap.slide.range === 0) {
       desc += " (unlimited range)";
     } else {
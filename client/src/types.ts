@@ -1,4 +1,4 @@
-import type { MovementCap, PieceRule } from "./types/rules";
+import type { MovementCap, PieceRule, Terrain } from "./types/rules";
 
 // Matches your Rust 'PlayerId'
 export type PlayerId = string;
@@ -10,27 +10,79 @@ export type PieceType = string;
 export interface Piece {
   piece_type: PieceType;
   owner: PlayerId;
+  move_count: number;
+  damage: number; // hit points lost so far
+  recharge: number; // own turns left before it can act again
+  actions: number; // moves and shots made so far, counted against max_moves
   capabilities?: MovementCap[];
 }
 
-// Matches your Rust 'Board' type: [[Option<Piece>; 8]; 8]
+// Matches your Rust 'Board' type: Vec<Vec<Option<Piece>>>, indexed board[y][x]
 // In JS/TS, Option<T> becomes T | null
 export type Board = (Piece | null)[][];
 
 // Matches your Rust 'GameState'
 export interface GameState {
   board: Board;
+  width: number;
+  height: number;
+  topology: Topology;
+  grid: Grid;
+  terrain: Terrain[][]; // indexed like the board
   current_turn: PlayerId;
   players: [PlayerId, PlayerId];
   rules: Record<string, PieceRule>;
   disagreement_count: number;
   max_disagreements: number;
+  last_move: MoveRecord | null;
+  fog_of_war: boolean;
+  visible?: boolean[][]; // only on fog of war views, false where the square is hidden
+  drops: boolean;
+  hands: [PieceType[], PieceType[]]; // captured pieces, in the same order as `players`
+  spawn_points: [number, number]; // what each player has left to spawn with, same order
 }
 
+// Matches your Rust 'Topology': which edges of the board wrap around
+export type Topology = "flat" | "cylinder" | "torus";
+
+// Matches your Rust 'Grid': hex boards use axial (q, r) coordinates,
+// so each row sits half a cell further along than the one before
+export type Grid = "square" | "hex";
+
+// Matches your Rust 'MoveRecord'
+export interface MoveRecord {
+  player: PlayerId;
+  piece_type: PieceType;
+  from: [number, number];
+  to: [number, number];
+  kind: TurnKind;
+}
+
+// Matches your Rust 'TurnKind': drops and spawns have `from` equal to `to`,
+// shots and hits leave the attacker on `from`
+export type TurnKind = "move" | "shot" | "drop" | "spawn" | "hit";
+
 // The messages we send TO the server
 export type ClientMessage =
-  | { type: "join"; payload: { name: string } }
-  | { type: "move"; payload: { from: [number, number]; to: [number, number] } }
+  | {
+      type: "join";
+      payload: {
+        name: string;
+        board_size?: [number, number];
+        topology?: Topology;
+        grid?: Grid;
+        fog_of_war?: boolean;
+        drops?: boolean;
+        spawn_points?: number;
+        terrain?: [[number, number], Terrain][];
+      };
+    }
+  | {
+      type: "move";
+      payload: { from: [number, number]; to: [number, number]; promotion?: string };
+    }
+  | { type: "shoot"; payload: { from: [number, number]; target: [number, number] } }
+  | { type: "drop"; payload: { piece: string; at: [number, number] } }
   | { type: "propose_rule"; payload: { rule: PieceRule } }
   | { type: "spawn"; payload: { name: string; x: number; y: number } }
   | { type: "vote"; payload: { accept: boolean } };
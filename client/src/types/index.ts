@@ -2,5 +2,5 @@
  * Type exports for the Rule Builder system
  */
 
-export type { MovementCap, SlidePattern, PieceRule } from './rules';
+export type { MovementCap, MoveCondition, MoveMode, SlideDirection, SlidePattern, SlidePatternShorthand, PieceRule } from './rules';
 export { createSlideCapability, createLeapCapability } from './rules';
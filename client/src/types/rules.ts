@@ -3,12 +3,48 @@
  * Represents the movement capabilities and rules for chess-like pieces
  */
 
-export type SlidePattern = "linear" | "diagonal" | "omni";
+// on a hex grid linear covers the 6 neighbours and diagonal the 6 cells past each corner
+export type SlidePatternShorthand = "linear" | "diagonal" | "omni";
+
+/**
+ * One direction of a custom slide. `step` is from the first player's side
+ * (+y is forward) and is mirrored for the second player (turned half way round on hex grids).
+ */
+export interface SlideDirection {
+  step: [number, number];
+  range?: number; // 0 = only limited by the slide's range
+}
+
+export type SlidePattern = SlidePatternShorthand | { custom: SlideDirection[] };
+
+/**
+ * Restricts when a capability can be used
+ * - first_move: only if the piece has never moved (pawn double step)
+ */
+export type MoveCondition = "first_move";
+
+/**
+ * Whether a capability moves to empty squares, captures, or both (default).
+ * en_passant lands on a square the opponent's last moved piece of the same kind
+ * passed over, and takes that piece. swap trades places with a friendly piece,
+ * push shoves the piece on the target one square further without capturing it.
+ */
+export type MoveMode =
+  | "both"
+  | "move_only"
+  | "capture_only"
+  | "en_passant"
+  | "swap"
+  | "push";
 
 /**
  * Movement capability types - matches Rust externally tagged enum format
  * - slide: Continuous movement in a direction (like rook, bishop, queen)
+ * - hop: Slide over exactly one piece, the "screen" (like xiangqi cannon, grasshopper)
+ * - rider: Repeat a leap along its vector until blocked (like nightrider)
  * - leap: Jump to specific offsets (like knight)
+ * - shoot: Take an enemy in line of sight without moving (like an archer)
+ * - compound: Move and relocate a friendly partner in the same turn (like castling)
  *
  * Note: This uses externally tagged enum format to match Rust serde default.
  * JSON format examples:
@@ -22,11 +58,52 @@ export type MovementCap =
         range: number; // 0 = infinite, 1-8 otherwise
         can_jump: boolean;
         only_forward: boolean;
+        conditions?: MoveCondition[];
+        mode?: MoveMode;
+      };
+    }
+  | {
+      hop: {
+        pattern: SlidePattern;
+        range: number; // 0 = infinite
+        land_behind_screen?: boolean; // grasshopper: land right after the screen
+        conditions?: MoveCondition[];
+        mode?: MoveMode;
+      };
+    }
+  | {
+      rider: {
+        possibilities: [number, number][]; // Vectors to repeat
+        range: number; // max number of leaps, 0 = infinite
+        absolute?: boolean; // default: +y is forward for the owner
+        conditions?: MoveCondition[];
+        mode?: MoveMode;
       };
     }
   | {
       leap: {
         possibilities: [number, number][]; // List of relative (x,y) jumps
+        absolute?: boolean; // default: +y is forward for the owner, mirrored for player 2
+        lame?: boolean; // blocked by pieces on the squares it passes (xiangqi horse)
+        conditions?: MoveCondition[];
+        mode?: MoveMode;
+      };
+    }
+  | {
+      shoot: {
+        pattern: SlidePattern;
+        range: number; // 0 = infinite
+        conditions?: MoveCondition[];
+      };
+    }
+  | {
+      compound: {
+        offset: [number, number]; // where this piece goes
+        partner: string; // rule name of the piece that moves with it
+        partner_from: [number, number]; // relative to this piece's start
+        partner_to: [number, number]; // relative to this piece's start
+        safe_path?: boolean; // start, path and landing square must not be attacked
+        conditions?: MoveCondition[]; // checked against both pieces
       };
     };
 
@@ -35,6 +112,60 @@ export interface PieceRule {
   symbol: string;
   capabilities: MovementCap[];
   is_royal?: boolean; // Optional: if true, losing all these pieces ends the game
+  promotion?: Promotion | null;
+  on_capture?: CaptureEffect[]; // runs after this piece captures something
+  immune_to?: CaptureImmunity[]; // pieces that can never capture this one
+  protects_adjacent?: boolean; // friendly neighbours can't be captured
+  terrain?: Terrain[]; // water it can enter, forest it can slide through
+  spawn?: Spawn; // defaults to cost 1, back two ranks
+  combat?: Combat; // defaults to 1 hit point and 1 attack
+  cooldown?: number; // own turns to sit out after moving or shooting
+  max_moves?: number | null; // moves and shots allowed in the whole game, unlimited if unset
+}
+
+/**
+ * What a square is made of, holes can't be used at all,
+ * water and forest only by pieces whose rule lists them
+ */
+export type Terrain = "plain" | "hole" | "water" | "forest";
+
+/**
+ * Who may not capture a piece
+ * - piece: a single piece type, by rule name
+ * - leapers: anything with a leap or rider capability
+ * - royals: royal pieces
+ */
+export type CaptureImmunity = { piece: string } | "leapers" | "royals";
+
+/**
+ * Effects that run after a capture, in order
+ * - explode: removes everything within `radius` of the captured square (atomic chess)
+ */
+export type CaptureEffect = {
+  explode: {
+    radius: number; // 1 = the 8 neighbours
+    immune?: string[]; // piece names that survive the blast
+    self_destruct?: boolean; // the capturing piece goes down with it
+  };
+};
+
+/**
+ * What a piece can turn into once it reaches the far side of the board
+ */
+export interface Promotion {
+  ranks: number; // zone depth from the owner's far edge, 1 = last rank
+  into: string[]; // rule names the piece may become
+  mandatory?: boolean;
+}
+
+export interface Spawn {
+  cost: number; // spawn points it takes from the owner
+  ranks: number; // zone depth from the owner's home edge, 1 = back rank
+}
+
+export interface Combat {
+  hit_points: number; // the hit that uses up the last one captures the piece
+  attack: number; // damage dealt per hit
 }
 
 /**
@@ -68,13 +199,13 @@ export const createLeapCapability = (
 /**
  * Type guard to check if a capability is a Slide
  */
-export const isSlideCapability = (cap: MovementCap): cap is { slide: { pattern: SlidePattern; range: number; can_jump: boolean; only_forward: boolean } } => {
+export const isSlideCapability = (cap: MovementCap): cap is Extract<MovementCap, { slide: unknown }> => {
   return 'slide' in cap;
 };
 
 /**
  * Type guard to check if a capability is a Leap
  */
-export const isLeapCapability = (cap: MovementCap): cap is { leap: { possibilities: [number, number][] } } => {
+export const isLeapCapability = (cap: MovementCap): cap is Extract<MovementCap, { leap: unknown }> => {
   return 'leap' in cap;
 };
@@ -9,6 +9,17 @@ pub enum GameError {
     DestinationOccupiedBySelf { x: u8, y: u8 }, // cant capture your own piece
     ViolatesRule(String),
     EmptySource { x: u8, y: u8 },
+    LeavesRoyalInCheck, // move would expose your own royal piece
+    InvalidPromotion(String),
+    NotInHand(String), // dropping a piece type the player isn't holding
+    NotEnoughSpawnPoints { cost: u32, available: u32 },
+    OutsideSpawnZone { x: u8, y: u8 }, // too far from the player's home edge for this piece
+    ImmuneToCapture { piece: String, capturer: String }, // the target's rule refuses this capturer
+    ProtectedPiece { x: u8, y: u8, protector: String }, // a friendly neighbour shields it
+    BlockedByTerrain { x: u8, y: u8 }, // the piece cannot stand on that square
+    Recharging { x: u8, y: u8, turns_left: u32 }, // the piece's cooldown hasn't run out yet
+    OutOfMoves { x: u8, y: u8 },       // the piece has used up its whole move budget
     GameNotStarted,
+    InvalidBoardSize { width: u8, height: u8 },
     InvalidPlayer,
 }
@@ -3,11 +3,14 @@ pub mod rules;
 
 use std::collections::HashMap;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     errors::GameError,
-    rules::{MovementCap, PieceRule, SlidePattern},
+    rules::{
+        CaptureEffect, Combat, MoveCondition, MoveMode, MovementCap, PieceRule, Promotion,
+        SlidePattern, Spawn,
+    },
 };
 
 /*
@@ -23,23 +26,249 @@ pub struct PieceType(pub String);
 pub struct Piece {
     pub piece_type: PieceType,
     pub owner: PlayerId,
+    /// how many times this piece has moved, used by `MoveCondition`s
+    pub move_count: u32,
+    /// hit points lost so far, the rule's `Combat` says how many it has
+    pub damage: u32,
+    /// own turns left before it may act again, see `PieceRule::cooldown`
+    pub recharge: u32,
+    /// moves and shots it has made on its owner's turn, see `PieceRule::max_moves`
+    pub actions: u32,
 }
 
-pub type Board = [[Option<Piece>; 8]; 8];
+impl Piece {
+    pub fn new(piece_type: PieceType, owner: PlayerId) -> Self {
+        Self {
+            piece_type,
+            owner,
+            move_count: 0,
+            damage: 0,
+            recharge: 0,
+            actions: 0,
+        }
+    }
+}
+
+/// rows of squares, indexed `board[y][x]`, `height` rows of `width` squares
+pub type Board = Vec<Vec<Option<Piece>>>;
+
+/// What a validated move does besides taking `from` to `to`
+#[derive(Debug, Clone, PartialEq)]
+enum MoveKind {
+    Step,
+    /// lands on an empty square and removes the piece on `captured`
+    EnPassant {
+        captured: (u8, u8),
+    },
+    /// the piece stays put and removes whatever is on `to`
+    Shot,
+    /// castling style, a second piece relocates in the same move
+    Compound {
+        partner_from: (u8, u8),
+        partner_to: (u8, u8),
+    },
+    /// trades places with the friendly piece on `to`
+    Swap,
+    /// the piece on `to` is shoved to `pushed_to` instead of being taken
+    Push {
+        pushed_to: (u8, u8),
+    },
+    /// an attack the piece on `target` survives, the attacker stays put
+    Hit {
+        target: (u8, u8),
+    },
+}
+
+/// How the edges of the board behave
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    /// edges are walls
+    #[default]
+    Flat,
+    /// left and right edges are joined
+    Cylinder,
+    /// both pairs of edges are joined
+    Torus,
+}
+
+impl Topology {
+    pub fn wraps_x(&self) -> bool {
+        matches!(self, Topology::Cylinder | Topology::Torus)
+    }
+
+    pub fn wraps_y(&self) -> bool {
+        matches!(self, Topology::Torus)
+    }
+}
+
+/// The shape of the cells
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grid {
+    #[default]
+    Square,
+    /// hexagons in axial coordinates, (x, y) is (q, r) so the width x height
+    /// rectangle of cells becomes a rhombus of hexes
+    Hex,
+}
+
+impl Grid {
+    /// How many single steps it takes to cover `delta`
+    pub fn length(&self, delta: (i8, i8)) -> u8 {
+        let (dx, dy) = (delta.0.unsigned_abs(), delta.1.unsigned_abs());
+        match self {
+            Grid::Square => dx.max(dy),
+            Grid::Hex => dx.max(dy).max((delta.0 + delta.1).unsigned_abs()),
+        }
+    }
+
+    /// Turns an offset written for the first player around for the side moving in `fy`.
+    /// A mirror on squares, a half turn on hexes since mirroring y skews a hex offset
+    pub fn orient(&self, offset: (i8, i8), fy: i8) -> (i8, i8) {
+        match self {
+            Grid::Square => (offset.0, offset.1 * fy),
+            Grid::Hex => (offset.0 * fy, offset.1 * fy),
+        }
+    }
+}
+
+/// What a square is made of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Terrain {
+    #[default]
+    Plain,
+    /// not part of the board, nothing stands on it or moves through it
+    Hole,
+    /// only pieces at home in water can enter or cross it
+    Water,
+    /// anyone can stop in a forest, only pieces at home there slide through
+    Forest,
+}
+
+impl Terrain {
+    pub fn can_enter(&self, rule: Option<&PieceRule>) -> bool {
+        match self {
+            Terrain::Plain | Terrain::Forest => true,
+            Terrain::Hole => false,
+            Terrain::Water => rule.is_some_and(|rule| rule.terrain.contains(self)),
+        }
+    }
+
+    pub fn can_cross(&self, rule: Option<&PieceRule>) -> bool {
+        match self {
+            Terrain::Plain => true,
+            Terrain::Hole => false,
+            Terrain::Water | Terrain::Forest => {
+                rule.is_some_and(|rule| rule.terrain.contains(self))
+            }
+        }
+    }
+}
+
+/// How a finished game ended
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum GameOutcome {
+    RoyalsCaptured { winner: PlayerId },
+    Checkmate { winner: PlayerId },
+    Stalemate,
+}
+
+impl GameOutcome {
+    pub fn winner(&self) -> Option<&PlayerId> {
+        match self {
+            GameOutcome::RoyalsCaptured { winner } | GameOutcome::Checkmate { winner } => {
+                Some(winner)
+            }
+            GameOutcome::Stalemate => None,
+        }
+    }
+}
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct GameState {
     pub board: Board,
+    pub width: u8,
+    pub height: u8,
+    pub topology: Topology,
+    pub grid: Grid,
+    /// what each square is made of, indexed like the board
+    pub terrain: Vec<Vec<Terrain>>,
     #[serde(rename = "current_turn")]
     pub turn: PlayerId,
     pub players: (PlayerId, PlayerId),
     pub rules: HashMap<String, PieceRule>,
     pub disagreement_count: u8,
     pub max_disagreements: u8,
+    /// lets capabilities react to the opponent's previous move, e.g. en passant
+    pub last_move: Option<MoveRecord>,
+    /// players only see the squares their own pieces can reach, see view_for
+    pub fog_of_war: bool,
+    /// shogi style, captured pieces join the captor's hand and can be dropped back in
+    pub drops: bool,
+    /// pieces each player holds, in the same order as `players`
+    pub hands: (Vec<PieceType>, Vec<PieceType>),
+    /// what each player has left to spawn pieces with, in the same order as `players`
+    pub spawn_points: (u32, u32),
+}
+
+/// One player's view of the game, with the squares they can't see emptied
+#[derive(Clone, Serialize)]
+pub struct PlayerView {
+    #[serde(flatten)]
+    pub state: GameState,
+    /// indexed like the board, false where the fog hides the square
+    pub visible: Vec<Vec<bool>>,
+}
+
+/// A move that has been played
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MoveRecord {
+    pub player: PlayerId,
+    pub piece_type: PieceType,
+    pub from: (u8, u8),
+    pub to: (u8, u8),
+    pub kind: TurnKind,
+}
+
+/// What a recorded turn did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnKind {
+    /// the piece went from `from` to `to`, taking whatever was there
+    Move,
+    /// the piece on `from` shot whatever was on `to` and stayed put
+    Shot,
+    /// the piece came out of the player's hand onto `to`, `from` is the same square
+    Drop,
+    /// the piece was bought with spawn points and placed on `to`, `from` is the same square
+    Spawn,
+    /// the piece on `to` lost hit points but survived, the attacker stayed on `from`
+    Hit,
 }
 
 impl GameState {
-    pub fn new(player1: PlayerId, player2: PlayerId) -> Self {
+    /// smallest side that still fits the default setup, rooks, knights and a king
+    pub const MIN_BOARD_SIZE: u8 = 5;
+    /// keeps every coordinate and offset inside an i8
+    pub const MAX_BOARD_SIZE: u8 = 32;
+    /// spawn budget each player starts with unless the game is set up otherwise
+    pub const DEFAULT_SPAWN_POINTS: u32 = 10;
+
+    pub fn new(
+        player1: PlayerId,
+        player2: PlayerId,
+        width: u8,
+        height: u8,
+        grid: Grid,
+    ) -> Result<Self, GameError> {
+        let sizes = Self::MIN_BOARD_SIZE..=Self::MAX_BOARD_SIZE;
+        if !sizes.contains(&width) || !sizes.contains(&height) {
+            return Err(GameError::InvalidBoardSize { width, height });
+        }
+
         let mut rules = HashMap::new();
 
         rules.insert(
@@ -47,13 +276,55 @@ impl GameState {
             PieceRule {
                 name: "Pawn".to_string(),
                 symbol: "P".to_string(),
-                capabilities: vec![MovementCap::Slide {
-                    pattern: SlidePattern::FrontBack,
-                    range: 1,
-                    can_jump: false,
-                    only_forward: true,
-                }],
+                capabilities: vec![
+                    MovementCap::Slide {
+                        pattern: SlidePattern::FrontBack,
+                        range: 1,
+                        can_jump: false,
+                        only_forward: true,
+                        conditions: vec![],
+                        mode: MoveMode::MoveOnly,
+                    },
+                    // double step out of the starting square
+                    MovementCap::Slide {
+                        pattern: SlidePattern::FrontBack,
+                        range: 2,
+                        can_jump: false,
+                        only_forward: true,
+                        conditions: vec![MoveCondition::FirstMove],
+                        mode: MoveMode::MoveOnly,
+                    },
+                    MovementCap::Slide {
+                        pattern: SlidePattern::Diagonal,
+                        range: 1,
+                        can_jump: false,
+                        only_forward: true,
+                        conditions: vec![],
+                        mode: MoveMode::CaptureOnly,
+                    },
+                    MovementCap::Slide {
+                        pattern: SlidePattern::Diagonal,
+                        range: 1,
+                        can_jump: false,
+                        only_forward: true,
+                        conditions: vec![],
+                        mode: MoveMode::EnPassant,
+                    },
+                ],
                 is_royal: false,
+                promotion: Some(Promotion {
+                    ranks: 1,
+                    into: vec!["Rook".to_string(), "Knight".to_string()],
+                    mandatory: true,
+                }),
+                on_capture: vec![],
+                immune_to: vec![],
+                protects_adjacent: false,
+                terrain: vec![],
+                spawn: Spawn::default(),
+                combat: Combat::default(),
+                cooldown: 0,
+                max_moves: None,
             },
         );
 
@@ -63,18 +334,34 @@ impl GameState {
                 name: "Knight".to_string(),
                 symbol: "K".to_string(),
                 capabilities: vec![MovementCap::Leap {
-                    possibilities: vec![
-                        (1, 2), // L shapes
-                        (2, 1),
-                        (2, -1),
-                        (1, -2),
-                        (-1, -2),
-                        (-2, -1),
-                        (-2, 1),
-                        (-1, 2),
-                    ],
+                    possibilities: match grid {
+                        Grid::Square => vec![
+                            (1, 2), // L shapes
+                            (2, 1),
+                            (2, -1),
+                            (1, -2),
+                            (-1, -2),
+                            (-2, -1),
+                            (-2, 1),
+                            (-1, 2),
+                        ],
+                        Grid::Hex => rules::HEX_KNIGHT.to_vec(),
+                    },
+                    absolute: false,
+                    lame: false,
+                    conditions: vec![],
+                    mode: MoveMode::Both,
                 }],
                 is_royal: false,
+                promotion: None,
+                on_capture: vec![],
+                immune_to: vec![],
+                protects_adjacent: false,
+                terrain: vec![],
+                spawn: Spawn::default(),
+                combat: Combat::default(),
+                cooldown: 0,
+                max_moves: None,
             },
         );
 
@@ -88,90 +375,212 @@ impl GameState {
                     range: 0, // infinite
                     can_jump: false,
                     only_forward: false,
+                    conditions: vec![],
+                    mode: MoveMode::Both,
                 }],
                 is_royal: false,
+                promotion: None,
+                on_capture: vec![],
+                immune_to: vec![],
+                protects_adjacent: false,
+                terrain: vec![],
+                spawn: Spawn::default(),
+                combat: Combat::default(),
+                cooldown: 0,
+                max_moves: None,
             },
         );
 
+        // castling, king goes two squares and the rook hops over it. The rooks start in the
+        // corners, so how far away they are depends on the width, and a side where the king
+        // would land on or past its rook can't castle at all
+        let king_col = width as i8 / 2;
+        let castling = [(1, width as i8 - 1 - king_col), (-1, king_col)]
+            .into_iter()
+            .filter(|(_, rook_distance)| *rook_distance >= 3)
+            .map(|(side, rook_distance)| MovementCap::Compound {
+                offset: (2 * side, 0),
+                partner: "Rook".to_string(),
+                partner_from: (side * rook_distance, 0),
+                partner_to: (side, 0),
+                safe_path: true,
+                conditions: vec![MoveCondition::FirstMove],
+            });
+        let mut king_moves = vec![MovementCap::Slide {
+            pattern: SlidePattern::FrontBack,
+            range: 1,
+            can_jump: false,
+            only_forward: false,
+            conditions: vec![],
+            mode: MoveMode::Both,
+        }];
+        king_moves.extend(castling);
+
         rules.insert(
             "King".to_string(),
             PieceRule {
                 name: "King".to_string(),
                 symbol: "K".to_string(),
-                capabilities: vec![MovementCap::Slide {
-                    pattern: SlidePattern::FrontBack,
-                    range: 1,
-                    can_jump: false,
-                    only_forward: false,
-                }],
+                capabilities: king_moves,
                 is_royal: true, // King is now a royal piece
+                promotion: None,
+                on_capture: vec![],
+                immune_to: vec![],
+                protects_adjacent: false,
+                terrain: vec![],
+                spawn: Spawn::default(),
+                combat: Combat::default(),
+                cooldown: 0,
+                max_moves: None,
             },
         );
 
-        let mut board: Board = std::array::from_fn(|_| std::array::from_fn(|_| None));
-
-        // place the pieces
-        (0..8).for_each(|i| {
-            board[1][i] = Some(Piece {
-                piece_type: PieceType("Pawn".into()),
-                owner: player1.clone(),
-            });
-            if i == 0 || i == 7 {
-                board[0][i] = Some(Piece {
-                    piece_type: PieceType("Rook".into()),
-                    owner: player1.clone(),
-                });
-            }
-
-            if i == 1 || i == 6 {
-                board[0][i] = Some(Piece {
-                    piece_type: PieceType("Knight".into()),
-                    owner: player1.clone(),
-                });
-            }
-
-            if i == 4 {
-                board[0][i] = Some(Piece {
-                    piece_type: PieceType("King".into()),
-                    owner: player1.clone(),
-                });
-            }
-
-            board[6][i] = Some(Piece {
-                piece_type: PieceType("Pawn".into()),
-                owner: player2.clone(),
-            });
-
-            if i == 0 || i == 7 {
-                board[7][i] = Some(Piece {
-                    piece_type: PieceType("Rook".into()),
-                    owner: player2.clone(),
-                });
-            }
+        let mut board: Board = vec![vec![None; width as usize]; height as usize];
 
-            if i == 1 || i == 6 {
-                board[7][i] = Some(Piece {
-                    piece_type: PieceType("Knight".into()),
-                    owner: player2.clone(),
-                });
-            }
+        // place the pieces, back ranks spread out to whatever width we got
+        let (last_col, last_row) = (width as usize - 1, height as usize - 1);
+        (0..width as usize).for_each(|i| {
+            board[1][i] = Some(Piece::new(PieceType("Pawn".into()), player1.clone()));
+            board[last_row - 1][i] = Some(Piece::new(PieceType("Pawn".into()), player2.clone()));
 
-            if i == 4 {
-                board[7][i] = Some(Piece {
-                    piece_type: PieceType("King".into()),
-                    owner: player2.clone(),
-                });
-            }
+            let back = if i == 0 || i == last_col {
+                "Rook"
+            } else if i == 1 || i == last_col - 1 {
+                "Knight"
+            } else if i == width as usize / 2 {
+                "King"
+            } else {
+                return;
+            };
+            // hex offsets turn half way round for the second player, so its back rank
+            // is turned round too and castling still finds the rooks
+            let p2_col = if grid == Grid::Hex { last_col - i } else { i };
+            board[0][i] = Some(Piece::new(PieceType(back.into()), player1.clone()));
+            board[last_row][p2_col] = Some(Piece::new(PieceType(back.into()), player2.clone()));
         });
 
-        Self {
+        Ok(Self {
             board,
+            width,
+            height,
+            topology: Topology::Flat,
+            grid,
+            terrain: vec![vec![Terrain::Plain; width as usize]; height as usize],
             turn: player1.clone(),
             players: (player1, player2),
             rules,
             disagreement_count: 0,
             max_disagreements: 3,
+            last_move: None,
+            fog_of_war: false,
+            drops: false,
+            hands: (Vec::new(), Vec::new()),
+            spawn_points: (Self::DEFAULT_SPAWN_POINTS, Self::DEFAULT_SPAWN_POINTS),
+        })
+    }
+
+    /// Sets how the edges behave. On a torus the two back ranks would touch across the
+    /// wrapped edge with the kings side by side, so each army is pulled one rank towards
+    /// the middle, leaving two empty ranks between them, when the board is tall enough.
+    /// Meant for a fresh game, before any terrain goes down
+    pub fn set_topology(&mut self, topology: Topology) {
+        let already_wrapped = self.topology.wraps_y();
+        self.topology = topology;
+        if already_wrapped || !topology.wraps_y() || self.height < 6 {
+            return;
+        }
+
+        let last_row = self.height as usize - 1;
+        self.board[..=last_row / 2].rotate_right(1);
+        self.board[last_row / 2 + 1..].rotate_left(1);
+    }
+
+    /// Marks a square as `terrain`, refused if the piece standing there couldn't enter it
+    pub fn set_terrain(&mut self, square: (u8, u8), terrain: Terrain) -> Result<(), GameError> {
+        if square.0 >= self.width || square.1 >= self.height {
+            return Err(GameError::OutOfBounds {
+                x: square.0,
+                y: square.1,
+            });
+        }
+        if let Some(piece) = &self.board[square.1 as usize][square.0 as usize]
+            && !terrain.can_enter(self.rules.get(&piece.piece_type.0))
+        {
+            return Err(GameError::BlockedByTerrain {
+                x: square.0,
+                y: square.1,
+            });
         }
+        self.terrain[square.1 as usize][square.0 as usize] = terrain;
+        Ok(())
+    }
+
+    /// Whether a piece of type `piece_type` may stop on `square`
+    fn can_enter(&self, piece_type: &PieceType, square: (u8, u8)) -> bool {
+        self.terrain[square.1 as usize][square.0 as usize].can_enter(self.rules.get(&piece_type.0))
+    }
+
+    /// Whether `rule`'s piece can get past the terrain visited before the last of `steps`
+    /// repeats of `step`, the counterpart of count_blockers for the ground itself
+    fn terrain_crossable(
+        &self,
+        from: (u8, u8),
+        step: (i8, i8),
+        steps: u8,
+        rule: &PieceRule,
+    ) -> bool {
+        (1..steps as i8).all(|k| {
+            self.normalize(from.0 as i8 + k * step.0, from.1 as i8 + k * step.1)
+                .is_none_or(|(x, y)| self.terrain[y as usize][x as usize].can_cross(Some(rule)))
+        })
+    }
+
+    /// Brings a square that ran off the board back through any wrapping edge,
+    /// None if it went over a flat one
+    fn normalize(&self, x: i8, y: i8) -> Option<(u8, u8)> {
+        let fold = |v: i8, size: u8, wraps: bool| {
+            if wraps {
+                Some(v.rem_euclid(size as i8) as u8)
+            } else {
+                (0..size as i8).contains(&v).then_some(v as u8)
+            }
+        };
+        Some((
+            fold(x, self.width, self.topology.wraps_x())?,
+            fold(y, self.height, self.topology.wraps_y())?,
+        ))
+    }
+
+    /// Every (dx, dy) that leads from `from` to `to`, the straight one plus the ways
+    /// round through wrapping edges
+    fn deltas(&self, from: (u8, u8), to: (u8, u8)) -> Vec<(i8, i8)> {
+        let around = |d: i8, size: u8, wraps: bool| match (wraps, d.signum()) {
+            (true, 1) => vec![d, d - size as i8],
+            (true, -1) => vec![d, d + size as i8],
+            _ => vec![d],
+        };
+        let xs = around(
+            to.0 as i8 - from.0 as i8,
+            self.width,
+            self.topology.wraps_x(),
+        );
+        let ys = around(
+            to.1 as i8 - from.1 as i8,
+            self.height,
+            self.topology.wraps_y(),
+        );
+        xs.iter()
+            .flat_map(|dx| ys.iter().map(move |dy| (*dx, *dy)))
+            .collect()
+    }
+
+    /// Single steps between two cells, the short way round on wrapping edges
+    fn distance(&self, a: (u8, u8), b: (u8, u8)) -> u8 {
+        self.deltas(a, b)
+            .into_iter()
+            .map(|delta| self.grid.length(delta))
+            .min()
+            .unwrap_or(0)
     }
 
     pub fn apply_move(
@@ -179,197 +588,2524 @@ impl GameState {
         player_id: &PlayerId,
         from: (u8, u8),
         to: (u8, u8),
+        promotion: Option<String>,
     ) -> Result<(), GameError> {
+        // Check if it's the player's turn
+        if player_id != &self.turn {
+            return Err(GameError::NotYourTurn {
+                current_player: self.turn.0.clone(),
+            });
+        }
+
+        let kind = self.check_move(from, to)?;
+
         let piece = self.board[from.1 as usize][from.0 as usize]
             .as_ref()
-            .unwrap();
+            .ok_or(GameError::EmptySource {
+                x: from.0,
+                y: from.1,
+            })?;
 
-        let rule = self
-            .rules
-            .get(&piece.piece_type.0)
-            .ok_or(GameError::ViolatesRule(format!(
-                "Unknown piece: {}",
-                piece.piece_type.0
-            )))?;
+        if piece.owner != self.turn {
+            return Err(GameError::NotYourTurn {
+                current_player: self.turn.0.clone(),
+            });
+        }
 
-        let dx = to.0 as i8 - from.0 as i8;
-        let dy = to.1 as i8 - from.1 as i8;
+        self.check_ready(from)?;
 
-        let fy = if piece.owner == self.players.0 { 1 } else { -1 };
+        if self.leaves_royal_in_check(from, to, &kind) {
+            return Err(GameError::LeavesRoyalInCheck);
+        }
 
-        let mut valid = false;
-        for cap in &rule.capabilities {
-            match cap {
-                rules::MovementCap::Slide {
-                    pattern,
-                    range,
-                    can_jump,
-                    only_forward,
-                } => {
-                    if *only_forward {
-                        // realised -y for 2nd player and +y for first player
-                        if dy.signum() != 0 && dy.signum() != fy {
-                            continue;
-                        }
-                        if dy != 0 && dy.signum() != fy {
-                            continue;
-                        }
-                    }
+        // a piece that only wounds its target never reaches `to`
+        let hit = matches!(kind, MoveKind::Hit { .. });
+        let promote_to = if hit {
+            None
+        } else {
+            self.check_promotion(piece, to, promotion)?
+        };
 
-                    let dist = dx.abs().max(dy.abs()) as u8;
-                    if *range > 0 && dist > *range {
-                        continue;
-                    }
+        let piece_type = piece.piece_type.clone();
+        self.play(from, to, &kind);
+        self.last_move = Some(MoveRecord {
+            player: player_id.clone(),
+            piece_type,
+            from,
+            to,
+            kind: if hit { TurnKind::Hit } else { TurnKind::Move },
+        });
+        if let Some(piece_type) = promote_to
+            && let Some(moved) = &mut self.board[to.1 as usize][to.0 as usize]
+        {
+            moved.piece_type = piece_type;
+        }
 
-                    let matches = match pattern {
-                        rules::SlidePattern::FrontBack => dx == 0 || dy == 0,
-                        rules::SlidePattern::Diagonal => dx.abs() == dy.abs(),
-                        rules::SlidePattern::Omni => dx == 0 || dy == 0 || dx.abs() == dy.abs(),
-                    };
+        self.end_turn(Some(if hit { from } else { to }));
+        Ok(())
+    }
 
-                    if !matches {
-                        continue;
-                    }
+    /// Removes the enemy piece on `target` with a ranged capability, the shooter stays on `from`
+    pub fn apply_shot(
+        &mut self,
+        player_id: &PlayerId,
+        from: (u8, u8),
+        target: (u8, u8),
+    ) -> Result<(), GameError> {
+        if player_id != &self.turn {
+            return Err(GameError::NotYourTurn {
+                current_player: self.turn.0.clone(),
+            });
+        }
 
-                    if !*can_jump {
-                        if self.check_path_clear(from, to).is_err() {
-                            continue;
-                        }
-                    }
+        let kind = self.check_shot(from, target)?;
 
-                    valid = true;
-                    break;
-                }
-                rules::MovementCap::Leap { possibilities } => {
-                    if possibilities.contains(&(dx, dy)) {
-                        valid = true;
-                        break;
-                    }
-                }
-            }
+        let (piece, _) = self.piece_and_rule(from)?;
+        if piece.owner != self.turn {
+            return Err(GameError::NotYourTurn {
+                current_player: self.turn.0.clone(),
+            });
         }
 
-        if !valid {
-            return Err(GameError::ViolatesRule(
-                "Move not allowed by any rule".into(),
-            ));
+        self.check_ready(from)?;
+
+        if self.leaves_royal_in_check(from, target, &kind) {
+            return Err(GameError::LeavesRoyalInCheck);
         }
 
-        // Check if it's the player's turn
+        let piece_type = piece.piece_type.clone();
+        self.play(from, target, &kind);
+        self.last_move = Some(MoveRecord {
+            player: player_id.clone(),
+            piece_type,
+            from,
+            to: target,
+            kind: match kind {
+                MoveKind::Hit { .. } => TurnKind::Hit,
+                _ => TurnKind::Shot,
+            },
+        });
+
+        self.end_turn(Some(from));
+        Ok(())
+    }
+
+    /// Buys a new `piece_type` for `player_id` and places it inside the piece's spawn zone,
+    /// which takes the whole turn
+    pub fn apply_spawn(
+        &mut self,
+        player_id: &PlayerId,
+        piece_type: &str,
+        at: (u8, u8),
+    ) -> Result<(), GameError> {
         if player_id != &self.turn {
             return Err(GameError::NotYourTurn {
                 current_player: self.turn.0.clone(),
             });
         }
 
-        if from.0 > 7 || from.1 > 7 {
-            return Err(GameError::OutOfBounds {
-                // should probably have this as a Point(u8,u8)
-                x: from.0,
-                y: from.1,
-            });
-        }
-        if to.0 > 7 || to.1 > 7 {
-            return Err(GameError::OutOfBounds { x: to.0, y: to.1 });
+        self.check_spawn(player_id, piece_type, at)?;
+
+        let mut next = self.clone();
+        next.play_spawn(player_id, piece_type, at);
+        if next.is_in_check(player_id) {
+            return Err(GameError::LeavesRoyalInCheck);
         }
-        // what if , there is no piece at ( x , y )
-        let piece = match &self.board[from.1 as usize][from.0 as usize] {
-            Some(p) => p,
-            None => {
-                return Err(GameError::EmptySource {
-                    x: from.0,
-                    y: from.1,
-                });
-            }
-        };
 
-        if piece.owner != self.turn {
+        self.play_spawn(player_id, piece_type, at);
+        self.last_move = Some(MoveRecord {
+            player: player_id.clone(),
+            piece_type: PieceType(piece_type.to_string()),
+            from: at,
+            to: at,
+            kind: TurnKind::Spawn,
+        });
+
+        self.end_turn(None);
+        Ok(())
+    }
+
+    /// Puts a piece from `player_id`'s hand on an empty square, which takes the whole turn
+    pub fn apply_drop(
+        &mut self,
+        player_id: &PlayerId,
+        piece_type: &str,
+        at: (u8, u8),
+    ) -> Result<(), GameError> {
+        if player_id != &self.turn {
             return Err(GameError::NotYourTurn {
                 current_player: self.turn.0.clone(),
             });
         }
 
-        if let Some(dest_piece) = &self.board[to.1 as usize][to.0 as usize]
-            && dest_piece.owner == self.turn
-        {
-            return Err(GameError::DestinationOccupiedBySelf { x: to.0, y: to.1 });
+        self.check_drop(player_id, piece_type, at)?;
+
+        let mut next = self.clone();
+        next.play_drop(player_id, piece_type, at);
+        if next.is_in_check(player_id) {
+            return Err(GameError::LeavesRoyalInCheck);
         }
 
-        //capture
-        self.board[to.1 as usize][to.0 as usize] =
-            self.board[from.1 as usize][from.0 as usize].take();
+        self.play_drop(player_id, piece_type, at);
+        self.last_move = Some(MoveRecord {
+            player: player_id.clone(),
+            piece_type: PieceType(piece_type.to_string()),
+            from: at,
+            to: at,
+            kind: TurnKind::Drop,
+        });
 
-        self.turn = if self.turn == self.players.1 {
-            self.players.0.clone()
-        } else {
-            self.players.1.clone()
-        };
+        self.end_turn(None);
         Ok(())
     }
 
-    /// Check if a player has any royal pieces remaining on the board
-    pub fn has_royal_pieces(&self, player: &PlayerId) -> bool {
-        for row in &self.board {
-            for square in row {
-                if let Some(piece) = square {
-                    if piece.owner == *player {
-                        if let Some(rule) = self.rules.get(&piece.piece_type.0) {
-                            if rule.is_royal {
-                                return true;
-                            }
-                        }
+    /// Every destination the piece on `from` can reach, ignoring whose turn it is.
+    /// Moves that would expose the owner's royal pieces are left out, and so is
+    /// everything for a piece that is recharging or out of moves.
+    pub fn legal_moves_from(&self, from: (u8, u8)) -> impl Iterator<Item = (u8, u8)> + '_ {
+        let ready = self.check_ready(from).is_ok();
+        self.squares().filter(move |&to| {
+            ready
+                && self
+                    .check_move(from, to)
+                    .is_ok_and(|kind| !self.leaves_royal_in_check(from, to, &kind))
+        })
+    }
+
+    /// Every (from, to) pair available to `player`'s pieces
+    pub fn legal_moves(&self, player: &PlayerId) -> Vec<((u8, u8), (u8, u8))> {
+        self.owned_squares(player)
+            .flat_map(|from| self.legal_moves_from(from).map(move |to| (from, to)))
+            .collect()
+    }
+
+    /// Every (piece, square) `player` could afford to spawn
+    pub fn legal_spawns(&self, player: &PlayerId) -> Vec<(PieceType, (u8, u8))> {
+        let mut names: Vec<&String> = self.rules.keys().collect();
+        names.sort();
+
+        let mut scratch = None;
+        let mut spawns = Vec::new();
+        for name in names {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if self.spawn_is_safe(&mut scratch, player, name, (x, y)) {
+                        spawns.push((PieceType(name.clone()), (x, y)));
                     }
                 }
             }
         }
-        false
+        spawns
     }
 
-    /// Check if the game is over (a player has lost all their royal pieces)
-    pub fn check_game_over(&self) -> Option<PlayerId> {
-        let has_royal_rules = self.rules.values().any(|rule| rule.is_royal);
-
-        if !has_royal_rules {
-            return None;
+    /// Every (piece, square) `player` could drop from their hand
+    pub fn legal_drops(&self, player: &PlayerId) -> Vec<(PieceType, (u8, u8))> {
+        let mut held: Vec<&PieceType> = Vec::new();
+        for piece_type in self.hand(player) {
+            if !held.contains(&piece_type) {
+                held.push(piece_type);
+            }
         }
 
-        let p1_has_royal = self.has_royal_pieces(&self.players.0);
-        let p2_has_royal = self.has_royal_pieces(&self.players.1);
-
-        if !p1_has_royal && p2_has_royal {
-            // Player 1 lost all royal pieces, Player 2 wins
-            return Some(self.players.1.clone());
-        }
-        if !p2_has_royal && p1_has_royal {
-            // Player 2 lost all royal pieces, Player 1 wins
-            return Some(self.players.0.clone());
+        let mut scratch = None;
+        let mut drops = Vec::new();
+        for piece_type in held {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if self.drop_is_safe(&mut scratch, player, &piece_type.0, (x, y)) {
+                        drops.push((piece_type.clone(), (x, y)));
+                    }
+                }
+            }
         }
-        None
+        drops
     }
 
-    fn check_path_clear(&self, from: (u8, u8), to: (u8, u8)) -> Result<(), GameError> {
-        let dx = to.0 as i8 - from.0 as i8;
-        let dy = to.1 as i8 - from.1 as i8;
+    /// Whether `player` has anything at all to do on their turn, stops at the first
+    /// legal action instead of listing them all like the legal_* methods
+    fn has_any_legal_action(&self, player: &PlayerId) -> bool {
+        self.has_any_legal_move(player)
+            || self.has_any_legal_shot(player)
+            || self.has_any_legal_drop(player)
+            || self.has_any_legal_spawn(player)
+    }
 
-        let step_x = dx.signum();
-        let step_y = dy.signum();
+    fn has_any_legal_move(&self, player: &PlayerId) -> bool {
+        self.owned_squares(player)
+            .any(|from| self.legal_moves_from(from).next().is_some())
+    }
 
-        // if its not straigjt line or diag its a jump (knight)
-        if dx.abs() != dy.abs() && dx != 0 && dy != 0 {
-            return Ok(());
-        }
+    fn has_any_legal_shot(&self, player: &PlayerId) -> bool {
+        self.owned_squares(player)
+            .any(|from| self.legal_shots_from(from).next().is_some())
+    }
 
-        let mut curr_x = from.0 as i8 + step_x;
-        let mut curr_y = from.1 as i8 + step_y;
+    fn has_any_legal_drop(&self, player: &PlayerId) -> bool {
+        let mut scratch = None;
+        self.hand(player).iter().any(|piece_type| {
+            self.squares()
+                .any(|at| self.drop_is_safe(&mut scratch, player, &piece_type.0, at))
+        })
+    }
 
-        while (curr_x, curr_y) != (to.0 as i8, to.1 as i8) {
-            if self.board[curr_y as usize][curr_x as usize].is_some() {
-                return Err(GameError::ViolatesRule("Path is blocked".into()));
-            }
-            curr_x += step_x;
-            curr_y += step_y;
-        }
+    fn has_any_legal_spawn(&self, player: &PlayerId) -> bool {
+        let mut scratch = None;
+        self.rules.keys().any(|name| {
+            self.squares()
+                .any(|at| self.spawn_is_safe(&mut scratch, player, name, at))
+        })
+    }
+
+    /// Every square on the board, row by row
+    fn squares(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+    }
+
+    /// The squares holding one of `player`'s pieces
+    fn owned_squares<'a>(&'a self, player: &'a PlayerId) -> impl Iterator<Item = (u8, u8)> + 'a {
+        self.squares().filter(move |&(x, y)| {
+            matches!(
+                &self.board[y as usize][x as usize],
+                Some(piece) if piece.owner == *player
+            )
+        })
+    }
+
+    /// Whether dropping `piece_type` on `at` is legal. The position is only copied, into
+    /// `scratch`, once a drop passes the cheap checks, and put back after each try
+    fn drop_is_safe(
+        &self,
+        scratch: &mut Option<GameState>,
+        player: &PlayerId,
+        piece_type: &str,
+        at: (u8, u8),
+    ) -> bool {
+        if self.check_drop(player, piece_type, at).is_err() {
+            return false;
+        }
+        let next = scratch.get_or_insert_with(|| self.clone());
+        next.play_drop(player, piece_type, at);
+        let safe = !next.is_in_check(player);
+        next.board[at.1 as usize][at.0 as usize] = None;
+        next.hand_mut(player)
+            .push(PieceType(piece_type.to_string()));
+        safe
+    }
+
+    /// Like drop_is_safe, for spawning
+    fn spawn_is_safe(
+        &self,
+        scratch: &mut Option<GameState>,
+        player: &PlayerId,
+        piece_type: &str,
+        at: (u8, u8),
+    ) -> bool {
+        if self.check_spawn(player, piece_type, at).is_err() {
+            return false;
+        }
+        let next = scratch.get_or_insert_with(|| self.clone());
+        next.play_spawn(player, piece_type, at);
+        let safe = !next.is_in_check(player);
+        next.board[at.1 as usize][at.0 as usize] = None;
+        *next.spawn_points_mut(player) = self.spawn_points(player);
+        safe
+    }
+
+    /// The captured pieces `player` is holding
+    pub fn hand(&self, player: &PlayerId) -> &[PieceType] {
+        if *player == self.players.0 {
+            &self.hands.0
+        } else {
+            &self.hands.1
+        }
+    }
+
+    fn hand_mut(&mut self, player: &PlayerId) -> &mut Vec<PieceType> {
+        if *player == self.players.0 {
+            &mut self.hands.0
+        } else {
+            &mut self.hands.1
+        }
+    }
+
+    /// Everything about a drop except whether it leaves the player's royals in check
+    fn check_drop(
+        &self,
+        player: &PlayerId,
+        piece_type: &str,
+        at: (u8, u8),
+    ) -> Result<(), GameError> {
+        if !self.drops {
+            return Err(GameError::ViolatesRule(
+                "Drops are not enabled in this game".into(),
+            ));
+        }
+
+        if at.0 >= self.width || at.1 >= self.height {
+            return Err(GameError::OutOfBounds { x: at.0, y: at.1 });
+        }
+
+        if !self.hand(player).iter().any(|held| held.0 == piece_type) {
+            return Err(GameError::NotInHand(piece_type.to_string()));
+        }
+
+        if self.board[at.1 as usize][at.0 as usize].is_some() {
+            return Err(GameError::ViolatesRule(
+                "Pieces can only be dropped on empty squares".into(),
+            ));
+        }
+
+        let piece = Piece::new(PieceType(piece_type.to_string()), player.clone());
+        if !self.can_enter(&piece.piece_type, at) {
+            return Err(GameError::BlockedByTerrain { x: at.0, y: at.1 });
+        }
+
+        // like shogi, nothing goes where it would be forced to promote straight away
+        if self
+            .promotion_zone(&piece, at)
+            .is_some_and(|promo| promo.mandatory)
+        {
+            return Err(GameError::ViolatesRule(format!(
+                "{} cannot be dropped where it would have to promote",
+                piece_type
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Takes one `piece_type` out of the hand and puts it on `at`, no checks
+    fn play_drop(&mut self, player: &PlayerId, piece_type: &str, at: (u8, u8)) {
+        let hand = self.hand_mut(player);
+        if let Some(index) = hand.iter().position(|held| held.0 == piece_type) {
+            let piece_type = hand.remove(index);
+            self.board[at.1 as usize][at.0 as usize] = Some(Piece::new(piece_type, player.clone()));
+        }
+    }
+
+    /// The spawn points `player` has left
+    pub fn spawn_points(&self, player: &PlayerId) -> u32 {
+        if *player == self.players.0 {
+            self.spawn_points.0
+        } else {
+            self.spawn_points.1
+        }
+    }
+
+    fn spawn_points_mut(&mut self, player: &PlayerId) -> &mut u32 {
+        if *player == self.players.0 {
+            &mut self.spawn_points.0
+        } else {
+            &mut self.spawn_points.1
+        }
+    }
+
+    /// Everything about a spawn except whether it leaves the player's royals in check
+    fn check_spawn(
+        &self,
+        player: &PlayerId,
+        piece_type: &str,
+        at: (u8, u8),
+    ) -> Result<(), GameError> {
+        if at.0 >= self.width || at.1 >= self.height {
+            return Err(GameError::OutOfBounds { x: at.0, y: at.1 });
+        }
+
+        let rule = self.rules.get(piece_type).ok_or_else(|| {
+            GameError::ViolatesRule(format!("No rule defined for {}", piece_type))
+        })?;
+
+        // more kings would make them pointless to hunt
+        if rule.is_royal {
+            return Err(GameError::ViolatesRule(format!(
+                "{} is royal and cannot be spawned",
+                piece_type
+            )));
+        }
+
+        let available = self.spawn_points(player);
+        if rule.spawn.cost > available {
+            return Err(GameError::NotEnoughSpawnPoints {
+                cost: rule.spawn.cost,
+                available,
+            });
+        }
+
+        // zone is counted from the owner's home side
+        let in_zone = if *player == self.players.0 {
+            at.1 < rule.spawn.ranks
+        } else {
+            at.1 >= self.height.saturating_sub(rule.spawn.ranks)
+        };
+        if !in_zone {
+            return Err(GameError::OutsideSpawnZone { x: at.0, y: at.1 });
+        }
+
+        if self.board[at.1 as usize][at.0 as usize].is_some() {
+            return Err(GameError::ViolatesRule(
+                "Pieces can only be spawned on empty squares".into(),
+            ));
+        }
+
+        let piece = Piece::new(PieceType(piece_type.to_string()), player.clone());
+        if !self.can_enter(&piece.piece_type, at) {
+            return Err(GameError::BlockedByTerrain { x: at.0, y: at.1 });
+        }
+
+        if self
+            .promotion_zone(&piece, at)
+            .is_some_and(|promo| promo.mandatory)
+        {
+            return Err(GameError::ViolatesRule(format!(
+                "{} cannot be spawned where it would have to promote",
+                piece_type
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Pays for a `piece_type` and puts it on `at`, no checks
+    fn play_spawn(&mut self, player: &PlayerId, piece_type: &str, at: (u8, u8)) {
+        let cost = self.rules.get(piece_type).map_or(0, |rule| rule.spawn.cost);
+        let points = self.spawn_points_mut(player);
+        *points = points.saturating_sub(cost);
+        self.board[at.1 as usize][at.0 as usize] = Some(Piece::new(
+            PieceType(piece_type.to_string()),
+            player.clone(),
+        ));
+    }
+
+    /// Every enemy the piece on `from` can shoot, ignoring whose turn it is
+    pub fn legal_shots_from(&self, from: (u8, u8)) -> impl Iterator<Item = (u8, u8)> + '_ {
+        let ready = self.check_ready(from).is_ok();
+        self.squares().filter(move |&target| {
+            ready
+                && self
+                    .check_shot(from, target)
+                    .is_ok_and(|kind| !self.leaves_royal_in_check(from, target, &kind))
+        })
+    }
+
+    /// Every (from, target) shot available to `player`'s pieces
+    pub fn legal_shots(&self, player: &PlayerId) -> Vec<((u8, u8), (u8, u8))> {
+        self.owned_squares(player)
+            .flat_map(|from| {
+                self.legal_shots_from(from)
+                    .map(move |target| (from, target))
+            })
+            .collect()
+    }
+
+    /// Works out what `piece` turns into when it lands on `to`, if anything
+    fn check_promotion(
+        &self,
+        piece: &Piece,
+        to: (u8, u8),
+        promotion: Option<String>,
+    ) -> Result<Option<PieceType>, GameError> {
+        let in_zone = self.promotion_zone(piece, to);
+
+        match (in_zone, promotion) {
+            (None, None) => Ok(None),
+            (None, Some(_)) => Err(GameError::InvalidPromotion(
+                "Piece cannot promote on this square".into(),
+            )),
+            (Some(promo), None) if promo.mandatory => Err(GameError::InvalidPromotion(format!(
+                "{} must promote, choose one of {:?}",
+                piece.piece_type.0, promo.into
+            ))),
+            (Some(_), None) => Ok(None),
+            (Some(promo), Some(target)) => {
+                if !promo.into.contains(&target) || !self.rules.contains_key(&target) {
+                    return Err(GameError::InvalidPromotion(format!(
+                        "{} cannot promote to {}",
+                        piece.piece_type.0, target
+                    )));
+                }
+                Ok(Some(PieceType(target)))
+            }
+        }
+    }
+
+    /// The promotion `piece` would get standing on `to`, if `to` is in its zone
+    fn promotion_zone(&self, piece: &Piece, to: (u8, u8)) -> Option<&Promotion> {
+        self.rules
+            .get(&piece.piece_type.0)
+            .and_then(|rule| rule.promotion.as_ref())
+            .filter(|promo| {
+                // zone is counted from the owner's far side
+                if piece.owner == self.players.0 {
+                    to.1 >= self.height.saturating_sub(promo.ranks)
+                } else {
+                    to.1 < promo.ranks
+                }
+            })
+    }
+
+    /// The piece on `from` and the rule it plays by
+    fn piece_and_rule(&self, from: (u8, u8)) -> Result<(&Piece, &PieceRule), GameError> {
+        if from.0 >= self.width || from.1 >= self.height {
+            return Err(GameError::OutOfBounds {
+                // should probably have this as a Point(u8,u8)
+                x: from.0,
+                y: from.1,
+            });
+        }
+        // what if , there is no piece at ( x , y )
+        let piece = match &self.board[from.1 as usize][from.0 as usize] {
+            Some(p) => p,
+            None => {
+                return Err(GameError::EmptySource {
+                    x: from.0,
+                    y: from.1,
+                });
+            }
+        };
+
+        let rule = self
+            .rules
+            .get(&piece.piece_type.0)
+            .ok_or(GameError::ViolatesRule(format!(
+                "Unknown piece: {}",
+                piece.piece_type.0
+            )))?;
+
+        Ok((piece, rule))
+    }
+
+    /// Whether the piece on `from` has recharged and still has moves left to make
+    fn check_ready(&self, from: (u8, u8)) -> Result<(), GameError> {
+        let Ok((piece, rule)) = self.piece_and_rule(from) else {
+            return Ok(());
+        };
+        if piece.recharge > 0 {
+            return Err(GameError::Recharging {
+                x: from.0,
+                y: from.1,
+                turns_left: piece.recharge,
+            });
+        }
+        if rule.max_moves.is_some_and(|max| piece.actions >= max) {
+            return Err(GameError::OutOfMoves {
+                x: from.0,
+                y: from.1,
+            });
+        }
+        Ok(())
+    }
+
+    /// Passes the turn on, counting down the mover's cooldowns first and then
+    /// charging the piece that acted, now standing on `actor`
+    fn end_turn(&mut self, actor: Option<(u8, u8)>) {
+        let player = self.turn.clone();
+        for piece in self.board.iter_mut().flatten().flatten() {
+            if piece.owner == player {
+                piece.recharge = piece.recharge.saturating_sub(1);
+            }
+        }
+
+        if let Some(at) = actor {
+            let cooldown = self.board[at.1 as usize][at.0 as usize]
+                .as_ref()
+                .and_then(|piece| self.rules.get(&piece.piece_type.0))
+                .map_or(0, |rule| rule.cooldown);
+            if let Some(piece) = &mut self.board[at.1 as usize][at.0 as usize] {
+                piece.recharge = cooldown;
+                piece.actions += 1;
+            }
+        }
+
+        self.turn = self.opponent(&player).clone();
+    }
+
+    /// Validates shooting the enemy on `target` from `from` without moving, turn order is left to the caller
+    fn check_shot(&self, from: (u8, u8), target: (u8, u8)) -> Result<MoveKind, GameError> {
+        let (piece, _) = self.piece_and_rule(from)?;
+
+        if target.0 >= self.width || target.1 >= self.height {
+            return Err(GameError::OutOfBounds {
+                x: target.0,
+                y: target.1,
+            });
+        }
+
+        match &self.board[target.1 as usize][target.0 as usize] {
+            None => return Err(GameError::ViolatesRule("Nothing to shoot at".into())),
+            Some(victim) if victim.owner == piece.owner => {
+                return Err(GameError::DestinationOccupiedBySelf {
+                    x: target.0,
+                    y: target.1,
+                });
+            }
+            Some(_) => {}
+        }
+
+        if !self.in_sight(from, target) {
+            return Err(GameError::ViolatesRule(
+                "Shot not allowed by any rule".into(),
+            ));
+        }
+
+        self.check_capture(from, target)?;
+        if !self.is_lethal(from, target) {
+            return Ok(MoveKind::Hit { target });
+        }
+        Ok(MoveKind::Shot)
+    }
+
+    /// Whether one of the piece on `from`'s shoot capabilities covers `target` with a clear
+    /// line of sight, whatever is standing there
+    fn in_sight(&self, from: (u8, u8), target: (u8, u8)) -> bool {
+        let Ok((piece, rule)) = self.piece_and_rule(from) else {
+            return false;
+        };
+        let deltas = self.deltas(from, target);
+        let fy = if piece.owner == self.players.0 { 1 } else { -1 };
+
+        rule.capabilities.iter().any(|cap| {
+            let rules::MovementCap::Shoot { pattern, range, .. } = cap else {
+                return false;
+            };
+            cap.conditions()
+                .iter()
+                .all(|condition| condition.is_met(piece))
+                && deltas.iter().any(|delta| {
+                    Self::slide_steps(pattern, *delta, fy, self.grid).any(|(step, steps)| {
+                        (*range == 0 || steps <= *range)
+                            && self.count_blockers(from, step, steps) == 0
+                    })
+                })
+        })
+    }
+
+    /// Either a move or a shot from `from` could take whatever stands on `target`
+    fn can_take(&self, from: (u8, u8), target: (u8, u8)) -> bool {
+        // a piece that can't act next turn threatens nothing
+        if self.check_ready(from).is_err() {
+            return false;
+        }
+        // swaps and pushes land on the square without taking anything,
+        // and a hit the target survives doesn't take it either
+        matches!(
+            self.check_move(from, target),
+            Ok(MoveKind::Step | MoveKind::EnPassant { .. })
+        ) || matches!(self.check_shot(from, target), Ok(MoveKind::Shot))
+    }
+
+    /// Validates a single (from, to), including whether the piece it takes may be taken.
+    /// Turn order is left to the caller so this can be shared with move generation.
+    fn check_move(&self, from: (u8, u8), to: (u8, u8)) -> Result<MoveKind, GameError> {
+        let kind = self.move_kind(from, to)?;
+        let victim = match &kind {
+            MoveKind::Step => to,
+            MoveKind::EnPassant { captured } => *captured,
+            _ => return Ok(kind),
+        };
+        self.check_capture(from, victim)?;
+        // plain moves onto an empty square have nothing to hit
+        let occupied = self.board[victim.1 as usize][victim.0 as usize].is_some();
+        if occupied && !self.is_lethal(from, victim) {
+            return Ok(MoveKind::Hit { target: victim });
+        }
+        Ok(kind)
+    }
+
+    /// Whether the piece on `from` hits hard enough to finish off the one on `victim`
+    fn is_lethal(&self, from: (u8, u8), victim: (u8, u8)) -> bool {
+        let damage = self.board[victim.1 as usize][victim.0 as usize]
+            .as_ref()
+            .map_or(0, |piece| piece.damage);
+        self.combat(from).attack >= self.combat(victim).hit_points.saturating_sub(damage)
+    }
+
+    /// Hit points and attack of the piece on `square`, plain chess values if it has no rule
+    fn combat(&self, square: (u8, u8)) -> Combat {
+        self.board[square.1 as usize][square.0 as usize]
+            .as_ref()
+            .and_then(|piece| self.rules.get(&piece.piece_type.0))
+            .map(|rule| rule.combat.clone())
+            .unwrap_or_default()
+    }
+
+    /// Immunity on the victim's rule and protecting neighbours both stop a capture
+    fn check_capture(&self, from: (u8, u8), victim: (u8, u8)) -> Result<(), GameError> {
+        let (Some(capturer), Some(target)) = (
+            &self.board[from.1 as usize][from.0 as usize],
+            &self.board[victim.1 as usize][victim.0 as usize],
+        ) else {
+            return Ok(());
+        };
+
+        if let (Some(capturer_rule), Some(target_rule)) = (
+            self.rules.get(&capturer.piece_type.0),
+            self.rules.get(&target.piece_type.0),
+        ) && target_rule
+            .immune_to
+            .iter()
+            .any(|immunity| immunity.covers(capturer, capturer_rule))
+        {
+            return Err(GameError::ImmuneToCapture {
+                piece: target.piece_type.0.clone(),
+                capturer: capturer.piece_type.0.clone(),
+            });
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if (x, y) == victim || self.distance((x, y), victim) > 1 {
+                    continue;
+                }
+                if let Some(neighbour) = &self.board[y as usize][x as usize]
+                    && neighbour.owner == target.owner
+                    && self
+                        .rules
+                        .get(&neighbour.piece_type.0)
+                        .is_some_and(|rule| rule.protects_adjacent)
+                {
+                    return Err(GameError::ProtectedPiece {
+                        x: victim.0,
+                        y: victim.1,
+                        protector: neighbour.piece_type.0.clone(),
+                    });
+                }
+            }
+        }
 
         Ok(())
     }
+
+    /// Validates a single (from, to) against the board and the moving piece's capabilities.
+    fn move_kind(&self, from: (u8, u8), to: (u8, u8)) -> Result<MoveKind, GameError> {
+        let (piece, rule) = self.piece_and_rule(from)?;
+
+        if to.0 >= self.width || to.1 >= self.height {
+            return Err(GameError::OutOfBounds { x: to.0, y: to.1 });
+        }
+
+        if from == to {
+            return Err(GameError::ViolatesRule("Piece must move".into()));
+        }
+
+        if !self.can_enter(&piece.piece_type, to) {
+            return Err(GameError::BlockedByTerrain { x: to.0, y: to.1 });
+        }
+
+        let fy = if piece.owner == self.players.0 { 1 } else { -1 };
+
+        let (is_capture, is_friendly) = match &self.board[to.1 as usize][to.0 as usize] {
+            Some(dest_piece) => (
+                dest_piece.owner != piece.owner,
+                dest_piece.owner == piece.owner,
+            ),
+            None => (false, false),
+        };
+
+        // pushing only gets a say once nothing else could capture the piece on `to`
+        let (pushes, others): (Vec<_>, Vec<_>) = rule
+            .capabilities
+            .iter()
+            .partition(|cap| cap.mode() == MoveMode::Push);
+
+        // on a wrapping board the same square can be reached more than one way
+        let deltas = self.deltas(from, to);
+        let attempts = others
+            .into_iter()
+            .chain(pushes)
+            .flat_map(|cap| deltas.iter().map(move |delta| (cap, *delta)));
+
+        for (cap, (dx, dy)) in attempts {
+            if !cap.mode().allows(is_capture, is_friendly) {
+                continue;
+            }
+
+            if !cap
+                .conditions()
+                .iter()
+                .all(|condition| condition.is_met(piece))
+            {
+                continue;
+            }
+
+            // en passant moves to an empty square and takes the piece that just passed it
+            let step_kind = match cap.mode() {
+                MoveMode::EnPassant => match self.en_passant_victim(piece, to) {
+                    Some(captured) => MoveKind::EnPassant { captured },
+                    None => continue,
+                },
+                // the friendly piece has to be able to stand where we started
+                MoveMode::Swap => match &self.board[to.1 as usize][to.0 as usize] {
+                    Some(other) if self.can_enter(&other.piece_type, from) => MoveKind::Swap,
+                    _ => continue,
+                },
+                // the pushed piece carries on one square in the direction of travel
+                MoveMode::Push => {
+                    let Some(pushed_to) =
+                        self.normalize(to.0 as i8 + dx.signum(), to.1 as i8 + dy.signum())
+                    else {
+                        continue;
+                    };
+                    let pushed_in = self.board[to.1 as usize][to.0 as usize]
+                        .as_ref()
+                        .is_some_and(|pushed| self.can_enter(&pushed.piece_type, pushed_to));
+                    if self.board[pushed_to.1 as usize][pushed_to.0 as usize].is_some()
+                        || !pushed_in
+                    {
+                        continue;
+                    }
+                    MoveKind::Push { pushed_to }
+                }
+                _ => MoveKind::Step,
+            };
+
+            match cap {
+                rules::MovementCap::Slide {
+                    pattern,
+                    range,
+                    can_jump,
+                    only_forward,
+                    ..
+                } => {
+                    // realised -y for 2nd player and +y for first player, sideways isnt forward
+                    if *only_forward && dy.signum() != fy {
+                        continue;
+                    }
+
+                    // jumping pieces fly over the terrain as well
+                    let allowed =
+                        Self::slide_steps(pattern, (dx, dy), fy, self.grid).any(|(step, steps)| {
+                            (*range == 0 || steps <= *range)
+                                && (*can_jump
+                                    || (self.count_blockers(from, step, steps) == 0
+                                        && self.terrain_crossable(from, step, steps, rule)))
+                        });
+                    if allowed {
+                        return Ok(step_kind);
+                    }
+                }
+                rules::MovementCap::Hop {
+                    pattern,
+                    range,
+                    land_behind_screen,
+                    ..
+                } => {
+                    let allowed =
+                        Self::slide_steps(pattern, (dx, dy), fy, self.grid).any(|(step, steps)| {
+                            // exactly one screen between from and to
+                            if (*range > 0 && steps > *range)
+                                || self.count_blockers(from, step, steps) != 1
+                                || !self.terrain_crossable(from, step, steps, rule)
+                            {
+                                return false;
+                            }
+                            if !*land_behind_screen {
+                                return true;
+                            }
+                            self.normalize(to.0 as i8 - step.0, to.1 as i8 - step.1)
+                                .is_some_and(|(x, y)| self.board[y as usize][x as usize].is_some())
+                        });
+                    if allowed {
+                        return Ok(step_kind);
+                    }
+                }
+                rules::MovementCap::Rider {
+                    possibilities,
+                    range,
+                    absolute,
+                    ..
+                } => {
+                    let oy = if *absolute { 1 } else { fy };
+                    for offset in possibilities {
+                        let offset = self.grid.orient(*offset, oy);
+                        let Some(steps) = Self::steps_along((dx, dy), offset) else {
+                            continue;
+                        };
+                        if *range > 0 && steps > *range {
+                            continue;
+                        }
+                        // every landing square before `to` must be empty
+                        if self.count_blockers(from, offset, steps) == 0
+                            && self.terrain_crossable(from, offset, steps, rule)
+                        {
+                            return Ok(step_kind);
+                        }
+                    }
+                }
+                rules::MovementCap::Leap {
+                    possibilities,
+                    absolute,
+                    lame,
+                    ..
+                } => {
+                    // same trick as only_forward, flip y for the 2nd player
+                    let oy = if *absolute { 1 } else { fy };
+                    if possibilities
+                        .iter()
+                        .any(|offset| self.grid.orient(*offset, oy) == (dx, dy))
+                        && (!*lame || self.lame_path_clear(from, (dx, dy)))
+                    {
+                        return Ok(step_kind);
+                    }
+                }
+                // shots dont move the piece, see check_shot
+                rules::MovementCap::Shoot { .. } => {}
+                rules::MovementCap::Compound {
+                    offset,
+                    partner,
+                    partner_from,
+                    partner_to,
+                    safe_path,
+                    ..
+                } => {
+                    if self.grid.orient(*offset, fy) != (dx, dy) {
+                        continue;
+                    }
+                    let partner_delta = self.grid.orient(*partner_from, fy);
+                    let partner_to_delta = self.grid.orient(*partner_to, fy);
+                    let square = |rel: &(i8, i8)| {
+                        let rel = self.grid.orient(*rel, fy);
+                        self.normalize(from.0 as i8 + rel.0, from.1 as i8 + rel.1)
+                    };
+                    let (Some(partner_from), Some(partner_to)) =
+                        (square(partner_from), square(partner_to))
+                    else {
+                        continue;
+                    };
+
+                    // partner has to be ours, of the right kind and pass the same conditions
+                    let partner_ok = self.board[partner_from.1 as usize][partner_from.0 as usize]
+                        .as_ref()
+                        .is_some_and(|p| {
+                            p.owner == piece.owner
+                                && p.piece_type.0 == *partner
+                                && cap.conditions().iter().all(|c| c.is_met(p))
+                        });
+                    if !partner_ok {
+                        continue;
+                    }
+
+                    // nothing between the two pieces, and the partner's landing square is free
+                    if self.count_blockers_towards(from, partner_delta) > 0 {
+                        continue;
+                    }
+                    let partner_to_free = (partner_to == from
+                        || self.board[partner_to.1 as usize][partner_to.0 as usize].is_none())
+                        && self.can_enter(&PieceType(partner.clone()), partner_to);
+                    if !partner_to_free {
+                        continue;
+                    }
+
+                    // both pieces travel in a straight line, so the ground along the way
+                    // has to let each of them through
+                    let partner_path = (
+                        partner_to_delta.0 - partner_delta.0,
+                        partner_to_delta.1 - partner_delta.1,
+                    );
+                    let crossable = |start: (u8, u8), path: (i8, i8), rule: &PieceRule| {
+                        let steps = path.0.abs().max(path.1.abs()) as u8;
+                        self.terrain_crossable(
+                            start,
+                            (path.0.signum(), path.1.signum()),
+                            steps,
+                            rule,
+                        )
+                    };
+                    let partner_crosses = self.rules.get(partner).is_some_and(|partner_rule| {
+                        crossable(partner_from, partner_path, partner_rule)
+                    });
+                    if !crossable(from, (dx, dy), rule) || !partner_crosses {
+                        continue;
+                    }
+
+                    // castling rule, cant start, pass or land on an attacked square
+                    if *safe_path {
+                        let attacker = self.opponent(&piece.owner);
+                        let (sx, sy) = (dx.signum(), dy.signum());
+                        let mut passed = (0..=dx.abs().max(dy.abs())).filter_map(|k| {
+                            self.normalize(from.0 as i8 + k * sx, from.1 as i8 + k * sy)
+                        });
+                        if passed.any(|sq| self.is_attacked(sq, attacker)) {
+                            continue;
+                        }
+                    }
+
+                    return Ok(MoveKind::Compound {
+                        partner_from,
+                        partner_to,
+                    });
+                }
+            }
+        }
+
+        if is_friendly {
+            return Err(GameError::DestinationOccupiedBySelf { x: to.0, y: to.1 });
+        }
+
+        Err(GameError::ViolatesRule(
+            "Move not allowed by any rule".into(),
+        ))
+    }
+
+    /// The squares `player` can see, indexed like the board. Under fog of war that is
+    /// where their pieces stand and every square they could move to or shoot at.
+    /// Each target is tried both empty and holding a nameless enemy, so what really
+    /// stands there, or whether it could be taken, never changes what is visible
+    pub fn visible_squares(&self, player: &PlayerId) -> Vec<Vec<bool>> {
+        let mut visible = vec![vec![!self.fog_of_war; self.width as usize]; self.height as usize];
+        if !self.fog_of_war {
+            return visible;
+        }
+
+        let mut probe = self.clone();
+        let stand_in = Piece::new(PieceType(String::new()), self.opponent(player).clone());
+        for from in self.owned_squares(player) {
+            visible[from.1 as usize][from.0 as usize] = true;
+            for to in self.squares() {
+                if to == from || visible[to.1 as usize][to.0 as usize] {
+                    continue;
+                }
+                let actual = probe.board[to.1 as usize][to.0 as usize].take();
+                let reached = [None, Some(stand_in.clone())].into_iter().any(|occupant| {
+                    probe.board[to.1 as usize][to.0 as usize] = occupant;
+                    probe.move_kind(from, to).is_ok() || probe.in_sight(from, to)
+                });
+                probe.board[to.1 as usize][to.0 as usize] = actual;
+                visible[to.1 as usize][to.0 as usize] = reached;
+            }
+        }
+        visible
+    }
+
+    /// What `player` gets to see: the whole state, or under fog of war a copy with the
+    /// hidden squares emptied and the opponent's last move left out unless it ended in sight
+    pub fn view_for(&self, player: &PlayerId) -> PlayerView {
+        let visible = self.visible_squares(player);
+        let mut state = self.clone();
+        for (row, seen) in state.board.iter_mut().zip(&visible) {
+            for (square, seen) in row.iter_mut().zip(seen) {
+                if !seen {
+                    *square = None;
+                }
+            }
+        }
+        if let Some(last) = &state.last_move
+            && last.player != *player
+            && !visible[last.to.1 as usize][last.to.0 as usize]
+        {
+            state.last_move = None;
+        }
+        PlayerView { state, visible }
+    }
+
+    /// Check if a player has any royal pieces remaining on the board
+    pub fn has_royal_pieces(&self, player: &PlayerId) -> bool {
+        for row in &self.board {
+            for piece in row.iter().flatten() {
+                if piece.owner == *player
+                    && let Some(rule) = self.rules.get(&piece.piece_type.0)
+                    && rule.is_royal
+                {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Check if any of `player`'s royal pieces can be taken by an enemy piece
+    pub fn is_in_check(&self, player: &PlayerId) -> bool {
+        let enemies: Vec<(u8, u8)> = self
+            .squares()
+            .filter(|&(x, y)| {
+                self.board[y as usize][x as usize]
+                    .as_ref()
+                    .is_some_and(|piece| piece.owner != *player)
+            })
+            .collect();
+        self.owned_squares(player)
+            .filter(|&(x, y)| {
+                self.board[y as usize][x as usize]
+                    .as_ref()
+                    .and_then(|piece| self.rules.get(&piece.piece_type.0))
+                    .is_some_and(|rule| rule.is_royal)
+            })
+            .any(|royal| enemies.iter().any(|&enemy| self.can_take(enemy, royal)))
+    }
+
+    /// Could any of `attacker`'s pieces capture something standing on `square`
+    fn is_attacked(&self, square: (u8, u8), attacker: &PlayerId) -> bool {
+        // put a stand-in on empty squares so capture-only moves count
+        let mut probe;
+        let state = if self.board[square.1 as usize][square.0 as usize].is_some() {
+            self
+        } else {
+            probe = self.clone();
+            probe.board[square.1 as usize][square.0 as usize] = Some(Piece::new(
+                PieceType(String::new()),
+                self.opponent(attacker).clone(),
+            ));
+            &probe
+        };
+
+        state
+            .owned_squares(attacker)
+            .any(|from| state.can_take(from, square))
+    }
+
+    /// Check if the game is over: royal pieces captured, checkmate or stalemate
+    /// for the player whose turn it is
+    pub fn check_game_over(&self) -> Option<GameOutcome> {
+        let has_royal_rules = self.rules.values().any(|rule| rule.is_royal);
+
+        if has_royal_rules {
+            let p1_has_royal = self.has_royal_pieces(&self.players.0);
+            let p2_has_royal = self.has_royal_pieces(&self.players.1);
+
+            if !p1_has_royal && p2_has_royal {
+                // Player 1 lost all royal pieces, Player 2 wins
+                return Some(GameOutcome::RoyalsCaptured {
+                    winner: self.players.1.clone(),
+                });
+            }
+            if !p2_has_royal && p1_has_royal {
+                // Player 2 lost all royal pieces, Player 1 wins
+                return Some(GameOutcome::RoyalsCaptured {
+                    winner: self.players.0.clone(),
+                });
+            }
+        }
+
+        if self.has_any_legal_action(&self.turn) {
+            return None;
+        }
+
+        if self.is_in_check(&self.turn) {
+            Some(GameOutcome::Checkmate {
+                winner: self.opponent(&self.turn).clone(),
+            })
+        } else {
+            Some(GameOutcome::Stalemate)
+        }
+    }
+
+    fn opponent(&self, player: &PlayerId) -> &PlayerId {
+        if *player == self.players.0 {
+            &self.players.1
+        } else {
+            &self.players.0
+        }
+    }
+
+    /// Plays `from -> to` on a copy of the board and checks the mover's royals are still there and safe
+    fn leaves_royal_in_check(&self, from: (u8, u8), to: (u8, u8), kind: &MoveKind) -> bool {
+        let Some(piece) = &self.board[from.1 as usize][from.0 as usize] else {
+            return false;
+        };
+        let mut next = self.clone();
+        next.play(from, to, kind);
+        // blowing up your own last royal counts too
+        next.is_in_check(&piece.owner)
+            || (self.has_royal_pieces(&piece.owner) && !next.has_royal_pieces(&piece.owner))
+    }
+
+    /// The square of the enemy piece `piece` could take en passant by landing on `to`:
+    /// the last move was a piece of the same kind passing over `to`
+    fn en_passant_victim(&self, piece: &Piece, to: (u8, u8)) -> Option<(u8, u8)> {
+        let last = self.last_move.as_ref()?;
+        if last.kind != TurnKind::Move
+            || last.player == piece.owner
+            || last.piece_type != piece.piece_type
+        {
+            return None;
+        }
+
+        // the shortest way it could have come, edges may wrap
+        let (dx, dy) = self
+            .deltas(last.from, last.to)
+            .into_iter()
+            .min_by_key(|(dx, dy)| dx.abs().max(dy.abs()))?;
+        let step = (dx.signum(), dy.signum());
+        let steps = Self::steps_along((dx, dy), step)?;
+        let passed_over = (1..steps as i8).any(|k| {
+            self.normalize(
+                last.from.0 as i8 + k * step.0,
+                last.from.1 as i8 + k * step.1,
+            ) == Some(to)
+        });
+        passed_over.then_some(last.to)
+    }
+
+    /// Moves the pieces for an already validated move, capturing whatever is on `to`,
+    /// then runs the capturer's on-capture effects
+    fn play(&mut self, from: (u8, u8), to: (u8, u8), kind: &MoveKind) {
+        // with drops on, whatever gets taken changes sides and waits in the captor's hand
+        let taken = match kind {
+            MoveKind::Step | MoveKind::Shot if self.drops => Some(to),
+            MoveKind::EnPassant { captured } if self.drops => Some(*captured),
+            _ => None,
+        }
+        .and_then(|square| self.board[square.1 as usize][square.0 as usize].clone())
+        .zip(self.board[from.1 as usize][from.0 as usize].clone());
+        if let Some((taken, captor)) = taken {
+            self.hand_mut(&captor.owner).push(taken.piece_type);
+        }
+
+        let captured = match kind {
+            MoveKind::Step => self.board[to.1 as usize][to.0 as usize].is_some(),
+            MoveKind::EnPassant { .. } | MoveKind::Shot => true,
+            MoveKind::Compound { .. }
+            | MoveKind::Swap
+            | MoveKind::Push { .. }
+            | MoveKind::Hit { .. } => false,
+        };
+        // shooters stay where they are
+        let capturer_at = if *kind == MoveKind::Shot { from } else { to };
+
+        let partner = match kind {
+            MoveKind::Step => None,
+            MoveKind::Swap => self.board[to.1 as usize][to.0 as usize].take(),
+            MoveKind::Push { pushed_to } => {
+                self.board[pushed_to.1 as usize][pushed_to.0 as usize] =
+                    self.board[to.1 as usize][to.0 as usize].take();
+                None
+            }
+            MoveKind::Shot => {
+                self.board[to.1 as usize][to.0 as usize] = None;
+                None
+            }
+            MoveKind::EnPassant { captured } => {
+                self.board[captured.1 as usize][captured.0 as usize] = None;
+                None
+            }
+            MoveKind::Compound { partner_from, .. } => {
+                self.board[partner_from.1 as usize][partner_from.0 as usize].take()
+            }
+            MoveKind::Hit { target } => {
+                let attack = self.combat(from).attack;
+                if let Some(wounded) = &mut self.board[target.1 as usize][target.0 as usize] {
+                    wounded.damage += attack;
+                }
+                None
+            }
+        };
+
+        if !matches!(kind, MoveKind::Shot | MoveKind::Hit { .. }) {
+            //capture
+            self.board[to.1 as usize][to.0 as usize] =
+                self.board[from.1 as usize][from.0 as usize].take();
+            if let Some(moved) = &mut self.board[to.1 as usize][to.0 as usize] {
+                moved.move_count += 1;
+            }
+        }
+
+        match (kind, partner) {
+            (MoveKind::Compound { partner_to, .. }, Some(mut partner)) => {
+                partner.move_count += 1;
+                self.board[partner_to.1 as usize][partner_to.0 as usize] = Some(partner);
+            }
+            (MoveKind::Swap, Some(mut partner)) => {
+                partner.move_count += 1;
+                self.board[from.1 as usize][from.0 as usize] = Some(partner);
+            }
+            _ => {}
+        }
+
+        if captured {
+            self.resolve_capture(to, capturer_at);
+        }
+    }
+
+    /// Effect pipeline for a capture on `at` made by the piece standing on `capturer_at`
+    fn resolve_capture(&mut self, at: (u8, u8), capturer_at: (u8, u8)) {
+        let Some(effects) = self.board[capturer_at.1 as usize][capturer_at.0 as usize]
+            .as_ref()
+            .and_then(|piece| self.rules.get(&piece.piece_type.0))
+            .map(|rule| rule.on_capture.clone())
+        else {
+            return;
+        };
+
+        for effect in effects {
+            match effect {
+                CaptureEffect::Explode {
+                    radius,
+                    immune,
+                    self_destruct,
+                } => {
+                    for y in 0..self.height {
+                        for x in 0..self.width {
+                            if (x, y) == capturer_at {
+                                continue;
+                            }
+                            let dist = self.distance((x, y), at);
+                            let square = &mut self.board[y as usize][x as usize];
+                            if dist <= radius
+                                && square
+                                    .as_ref()
+                                    .is_some_and(|p| !immune.contains(&p.piece_type.0))
+                            {
+                                *square = None;
+                            }
+                        }
+                    }
+                    if self_destruct {
+                        self.board[capturer_at.1 as usize][capturer_at.0 as usize] = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every (step, steps) of `pattern` that lands on `delta`.
+    /// Custom directions are written for the first player, so they are turned round by `fy`
+    fn slide_steps(
+        pattern: &SlidePattern,
+        delta: (i8, i8),
+        fy: i8,
+        grid: Grid,
+    ) -> impl Iterator<Item = ((i8, i8), u8)> {
+        pattern
+            .directions(grid)
+            .into_iter()
+            .filter_map(move |direction| {
+                let step = grid.orient(direction.step, fy);
+                let steps = Self::steps_along(delta, step)?;
+                (direction.range == 0 || steps <= direction.range).then_some((step, steps))
+            })
+    }
+
+    /// How many repeats of `step` it takes to cover `delta`, if it lines up at all
+    fn steps_along(delta: (i8, i8), step: (i8, i8)) -> Option<u8> {
+        if step == (0, 0) {
+            return None;
+        }
+        let steps = if step.0 != 0 {
+            delta.0 / step.0
+        } else {
+            delta.1 / step.1
+        };
+        if steps > 0 && step.0 * steps == delta.0 && step.1 * steps == delta.1 {
+            Some(steps as u8)
+        } else {
+            None
+        }
+    }
+
+    /// Checks the squares a lame leap passes over: straight along the longer axis, then diagonally.
+    /// (1, 2) passes (0, 1) like the xiangqi horse, (2, 2) passes (1, 1) like the elephant.
+    /// Square grids only, PieceRule::validate keeps lame leaps off hex boards
+    fn lame_path_clear(&self, from: (u8, u8), offset: (i8, i8)) -> bool {
+        let (ax, ay) = (offset.0.abs(), offset.1.abs());
+        let straight = (ax - ay).abs();
+
+        let mut curr_x = from.0 as i8;
+        let mut curr_y = from.1 as i8;
+
+        for i in 1..ax.max(ay) {
+            if i > straight {
+                curr_x += offset.0.signum();
+                curr_y += offset.1.signum();
+            } else if ax > ay {
+                curr_x += offset.0.signum();
+            } else {
+                curr_y += offset.1.signum();
+            }
+
+            if self
+                .normalize(curr_x, curr_y)
+                .is_some_and(|(x, y)| self.board[y as usize][x as usize].is_some())
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Counts the pieces strictly between `from` and `from + delta` on a straight or diagonal line
+    fn count_blockers_towards(&self, from: (u8, u8), delta: (i8, i8)) -> u8 {
+        let step = (delta.0.signum(), delta.1.signum());
+        match Self::steps_along(delta, step) {
+            Some(steps) => self.count_blockers(from, step, steps),
+            None => 0,
+        }
+    }
+
+    /// Counts the pieces on the squares visited before the last of `steps` repeats of `step`
+    fn count_blockers(&self, from: (u8, u8), step: (i8, i8), steps: u8) -> u8 {
+        let mut blockers = 0;
+        for k in 1..steps as i8 {
+            if let Some((x, y)) =
+                self.normalize(from.0 as i8 + k * step.0, from.1 as i8 + k * step.1)
+                && self.board[y as usize][x as usize].is_some()
+            {
+                blockers += 1;
+            }
+        }
+        blockers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white() -> PlayerId {
+        PlayerId("white".into())
+    }
+
+    fn black() -> PlayerId {
+        PlayerId("black".into())
+    }
+
+    /// standard 8x8 game with the board cleared, spawning off so only the pieces put down count
+    fn empty_game() -> GameState {
+        let mut game = GameState::new(white(), black(), 8, 8, Grid::Square).unwrap();
+        game.board = vec![vec![None; 8]; 8];
+        game.spawn_points = (0, 0);
+        game
+    }
+
+    fn put(game: &mut GameState, at: (u8, u8), piece_type: &str, owner: PlayerId) {
+        game.board[at.1 as usize][at.0 as usize] =
+            Some(Piece::new(PieceType(piece_type.into()), owner));
+    }
+
+    fn piece_at(game: &GameState, at: (u8, u8)) -> Option<&str> {
+        game.board[at.1 as usize][at.0 as usize]
+            .as_ref()
+            .map(|piece| piece.piece_type.0.as_str())
+    }
+
+    /// registers a piece called `name` that moves with nothing but `capabilities`
+    fn add_rule(game: &mut GameState, name: &str, capabilities: Vec<MovementCap>) {
+        let mut rule = game.rules["Knight"].clone();
+        rule.name = name.into();
+        rule.capabilities = capabilities;
+        game.rules.insert(rule.name.clone(), rule);
+    }
+
+    #[test]
+    fn back_rank_mate_is_checkmate() {
+        let mut game = empty_game();
+        put(&mut game, (0, 0), "King", white());
+        put(&mut game, (7, 1), "Rook", black());
+        put(&mut game, (6, 0), "Rook", black());
+        put(&mut game, (7, 7), "King", black());
+
+        assert!(game.is_in_check(&white()));
+        assert_eq!(
+            game.check_game_over(),
+            Some(GameOutcome::Checkmate { winner: black() })
+        );
+    }
+
+    #[test]
+    fn no_moves_out_of_check_is_stalemate() {
+        let mut game = empty_game();
+        put(&mut game, (0, 0), "King", white());
+        put(&mut game, (7, 1), "Rook", black());
+        put(&mut game, (1, 7), "Rook", black());
+        put(&mut game, (7, 7), "King", black());
+
+        assert!(!game.is_in_check(&white()));
+        assert_eq!(game.check_game_over(), Some(GameOutcome::Stalemate));
+    }
+
+    #[test]
+    fn pinned_piece_cannot_leave_the_line() {
+        let mut game = empty_game();
+        put(&mut game, (0, 0), "King", white());
+        put(&mut game, (0, 1), "Rook", white());
+        put(&mut game, (0, 7), "Rook", black());
+        put(&mut game, (7, 7), "King", black());
+
+        assert_eq!(
+            game.apply_move(&white(), (0, 1), (1, 1), None),
+            Err(GameError::LeavesRoyalInCheck)
+        );
+        assert!(game.apply_move(&white(), (0, 1), (0, 5), None).is_ok());
+        assert_eq!(game.check_game_over(), None);
+    }
+
+    #[test]
+    fn pawn_on_the_last_rank_must_promote() {
+        let mut game = empty_game();
+        put(&mut game, (7, 0), "King", white());
+        put(&mut game, (7, 7), "King", black());
+        put(&mut game, (0, 6), "Pawn", white());
+
+        assert!(matches!(
+            game.apply_move(&white(), (0, 6), (0, 7), None),
+            Err(GameError::InvalidPromotion(_))
+        ));
+        assert!(matches!(
+            game.apply_move(&white(), (0, 6), (0, 7), Some("King".into())),
+            Err(GameError::InvalidPromotion(_))
+        ));
+        assert!(
+            game.apply_move(&white(), (0, 6), (0, 7), Some("Rook".into()))
+                .is_ok()
+        );
+        assert_eq!(piece_at(&game, (0, 7)), Some("Rook"));
+    }
+
+    #[test]
+    fn king_castles_both_ways_unless_the_path_is_attacked() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (0, 0), "Rook", white());
+        put(&mut game, (7, 0), "Rook", white());
+        put(&mut game, (4, 7), "King", black());
+
+        let moves = game.legal_moves_from((4, 0)).collect::<Vec<_>>();
+        assert!(moves.contains(&(6, 0)) && moves.contains(&(2, 0)));
+
+        // the king would pass through (5, 0)
+        put(&mut game, (5, 5), "Rook", black());
+        let moves = game.legal_moves_from((4, 0)).collect::<Vec<_>>();
+        assert!(!moves.contains(&(6, 0)) && moves.contains(&(2, 0)));
+
+        game.board[5][5] = None;
+        assert!(game.apply_move(&white(), (4, 0), (6, 0), None).is_ok());
+        assert_eq!(piece_at(&game, (6, 0)), Some("King"));
+        assert_eq!(piece_at(&game, (5, 0)), Some("Rook"));
+        assert_eq!(piece_at(&game, (7, 0)), None);
+    }
+
+    #[test]
+    fn king_that_has_moved_cannot_castle() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (7, 0), "Rook", white());
+        put(&mut game, (4, 7), "King", black());
+        game.board[0][4].as_mut().unwrap().move_count = 1;
+
+        assert!(!game.legal_moves_from((4, 0)).any(|to| to == (6, 0)));
+    }
+
+    #[test]
+    fn pawn_takes_a_double_step_en_passant_only_straight_away() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        put(&mut game, (3, 4), "Pawn", white());
+        put(&mut game, (2, 6), "Pawn", black());
+        put(&mut game, (0, 6), "Pawn", black());
+        game.board[4][3].as_mut().unwrap().move_count = 2;
+        game.turn = black();
+
+        game.apply_move(&black(), (2, 6), (2, 4), None).unwrap();
+        assert!(game.legal_moves_from((3, 4)).any(|to| to == (2, 5)));
+        game.apply_move(&white(), (3, 4), (2, 5), None).unwrap();
+        assert_eq!(piece_at(&game, (2, 4)), None);
+        assert_eq!(piece_at(&game, (2, 5)), Some("Pawn"));
+
+        // a single step can't be taken that way
+        put(&mut game, (1, 5), "Pawn", white());
+        game.apply_move(&black(), (0, 6), (0, 5), None).unwrap();
+        assert!(!game.legal_moves_from((1, 5)).any(|to| to == (0, 6)));
+    }
+
+    #[test]
+    fn rules_with_offsets_larger_than_any_board_are_rejected() {
+        let game = empty_game();
+        let mut rule = game.rules["Knight"].clone();
+        assert!(rule.validate(Grid::Square).is_ok());
+
+        rule.capabilities = vec![MovementCap::Leap {
+            possibilities: vec![(0, -128)],
+            absolute: false,
+            lame: false,
+            conditions: vec![],
+            mode: MoveMode::Both,
+        }];
+        assert!(matches!(
+            rule.validate(Grid::Square),
+            Err(GameError::ViolatesRule(_))
+        ));
+
+        rule.capabilities = vec![MovementCap::Slide {
+            pattern: SlidePattern::Custom(vec![rules::SlideDirection {
+                step: (0, 40),
+                range: 1,
+            }]),
+            range: 1,
+            can_jump: false,
+            only_forward: false,
+            conditions: vec![],
+            mode: MoveMode::Both,
+        }];
+        assert!(rule.validate(Grid::Square).is_err());
+    }
+
+    #[test]
+    fn tough_piece_survives_until_its_hit_points_run_out() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        let mut ogre = game.rules["Knight"].clone();
+        ogre.name = "Ogre".into();
+        ogre.combat.hit_points = 2;
+        game.rules.insert(ogre.name.clone(), ogre);
+        put(&mut game, (0, 0), "Rook", white());
+        put(&mut game, (0, 5), "Ogre", black());
+
+        game.apply_move(&white(), (0, 0), (0, 5), None).unwrap();
+        assert_eq!(piece_at(&game, (0, 0)), Some("Rook"));
+        assert_eq!(game.board[5][0].as_ref().unwrap().damage, 1);
+        assert_eq!(game.last_move.as_ref().unwrap().kind, TurnKind::Hit);
+
+        game.apply_move(&black(), (4, 7), (3, 7), None).unwrap();
+        game.apply_move(&white(), (0, 0), (0, 5), None).unwrap();
+        assert_eq!(piece_at(&game, (0, 0)), None);
+        assert_eq!(piece_at(&game, (0, 5)), Some("Rook"));
+    }
+
+    #[test]
+    fn moves_onto_empty_squares_are_never_hits() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        put(&mut game, (1, 0), "Knight", white());
+        game.rules.get_mut("Knight").unwrap().combat.attack = 0;
+
+        game.apply_move(&white(), (1, 0), (2, 2), None).unwrap();
+        assert_eq!(piece_at(&game, (2, 2)), Some("Knight"));
+        assert_eq!(game.last_move.as_ref().unwrap().kind, TurnKind::Move);
+    }
+
+    #[test]
+    fn rules_without_hit_points_or_attack_are_rejected() {
+        let game = empty_game();
+        let mut rule = game.rules["Knight"].clone();
+        rule.combat.attack = 0;
+        assert!(rule.validate(Grid::Square).is_err());
+        rule.combat = Combat {
+            hit_points: 0,
+            attack: 1,
+        };
+        assert!(rule.validate(Grid::Square).is_err());
+    }
+
+    #[test]
+    fn castling_reaches_the_rooks_on_wider_boards() {
+        let mut game = GameState::new(white(), black(), 10, 8, Grid::Square).unwrap();
+        for x in 1..9 {
+            if x != 5 {
+                game.board[0][x] = None;
+            }
+        }
+
+        let moves = game.legal_moves_from((5, 0)).collect::<Vec<_>>();
+        assert!(moves.contains(&(7, 0)) && moves.contains(&(3, 0)));
+        game.apply_move(&white(), (5, 0), (3, 0), None).unwrap();
+        assert_eq!(piece_at(&game, (4, 0)), Some("Rook"));
+        assert_eq!(piece_at(&game, (0, 0)), None);
+    }
+
+    #[test]
+    fn terrain_cannot_swallow_a_starting_piece() {
+        let mut game = GameState::new(white(), black(), 8, 8, Grid::Square).unwrap();
+        assert_eq!(
+            game.set_terrain((4, 0), Terrain::Hole),
+            Err(GameError::BlockedByTerrain { x: 4, y: 0 })
+        );
+        assert_eq!(piece_at(&game, (4, 0)), Some("King"));
+        assert!(game.set_terrain((4, 1), Terrain::Water).is_err());
+        assert!(game.set_terrain((4, 1), Terrain::Forest).is_ok());
+        assert!(game.set_terrain((4, 4), Terrain::Hole).is_ok());
+    }
+
+    #[test]
+    fn spawning_a_blocker_counts_as_a_way_out_of_mate() {
+        let mut game = empty_game();
+        put(&mut game, (0, 0), "King", white());
+        put(&mut game, (7, 1), "Rook", black());
+        put(&mut game, (6, 0), "Rook", black());
+        put(&mut game, (7, 7), "King", black());
+        game.spawn_points = (1, 0);
+
+        assert_eq!(game.check_game_over(), None);
+        assert_eq!(
+            game.apply_spawn(&white(), "Rook", (0, 4)),
+            Err(GameError::OutsideSpawnZone { x: 0, y: 4 })
+        );
+        game.apply_spawn(&white(), "Rook", (3, 0)).unwrap();
+        assert_eq!(game.spawn_points(&white()), 0);
+        assert_eq!(game.turn, black());
+    }
+
+    #[test]
+    fn piece_waits_out_its_cooldown_and_stops_when_out_of_moves() {
+        let mut game = empty_game();
+        put(&mut game, (0, 0), "King", white());
+        put(&mut game, (7, 7), "King", black());
+        put(&mut game, (3, 3), "Rook", white());
+        put(&mut game, (5, 1), "Knight", white());
+        game.rules.get_mut("Rook").unwrap().cooldown = 2;
+        game.rules.get_mut("Knight").unwrap().max_moves = Some(1);
+
+        game.apply_move(&white(), (3, 3), (3, 4), None).unwrap();
+        game.apply_move(&black(), (7, 7), (7, 6), None).unwrap();
+        assert_eq!(
+            game.apply_move(&white(), (3, 4), (3, 3), None),
+            Err(GameError::Recharging {
+                x: 3,
+                y: 4,
+                turns_left: 2
+            })
+        );
+        assert!(game.legal_moves_from((3, 4)).next().is_none());
+
+        game.apply_move(&white(), (5, 1), (6, 3), None).unwrap();
+        game.apply_move(&black(), (7, 6), (7, 7), None).unwrap();
+        assert_eq!(
+            game.apply_move(&white(), (6, 3), (5, 1), None),
+            Err(GameError::OutOfMoves { x: 6, y: 3 })
+        );
+
+        game.apply_move(&white(), (0, 0), (1, 0), None).unwrap();
+        game.apply_move(&black(), (7, 7), (7, 6), None).unwrap();
+        assert!(game.apply_move(&white(), (3, 4), (3, 3), None).is_ok());
+    }
+
+    #[test]
+    fn recharging_piece_gives_no_check() {
+        let mut game = empty_game();
+        put(&mut game, (0, 0), "King", white());
+        put(&mut game, (3, 7), "King", black());
+        put(&mut game, (3, 3), "Rook", white());
+        game.board[3][3].as_mut().unwrap().recharge = 1;
+
+        assert!(!game.is_in_check(&black()));
+    }
+
+    #[test]
+    fn torus_keeps_the_kings_apart_across_the_wrapped_edge() {
+        let mut game = GameState::new(white(), black(), 8, 8, Grid::Square).unwrap();
+        game.set_topology(Topology::Torus);
+
+        assert_eq!(piece_at(&game, (4, 1)), Some("King"));
+        assert_eq!(piece_at(&game, (4, 6)), Some("King"));
+        assert!(
+            game.board[0]
+                .iter()
+                .chain(&game.board[7])
+                .all(Option::is_none)
+        );
+        assert!(!game.is_in_check(&white()) && !game.is_in_check(&black()));
+        assert!(!game.legal_moves_from((4, 1)).any(|to| to == (4, 6)));
+
+        // setting it again doesn't shift the armies a second time
+        game.set_topology(Topology::Torus);
+        assert_eq!(piece_at(&game, (4, 1)), Some("King"));
+    }
+
+    #[test]
+    fn short_torus_leaves_the_kings_touching() {
+        let mut game = GameState::new(white(), black(), 5, 5, Grid::Square).unwrap();
+        game.set_topology(Topology::Torus);
+        assert!(game.is_in_check(&white()));
+    }
+
+    fn fogged_game() -> GameState {
+        let mut game = empty_game();
+        game.fog_of_war = true;
+        put(&mut game, (0, 0), "King", white());
+        put(&mut game, (7, 7), "King", black());
+        put(&mut game, (3, 3), "Pawn", white());
+        game.board[3][3].as_mut().unwrap().move_count = 1;
+        game
+    }
+
+    #[test]
+    fn fog_shows_what_the_pieces_reach_whoever_stands_there() {
+        let game = fogged_game();
+        let visible = game.visible_squares(&white());
+        assert!(visible[4][3] && visible[4][2] && visible[4][4]);
+        assert!(!visible[5][3] && !visible[7][7]);
+
+        // an enemy on the pawn's diagonal, even one the pawn can't take, changes nothing
+        let mut occupied = game.clone();
+        put(&mut occupied, (2, 4), "Rook", black());
+        occupied.rules.get_mut("Rook").unwrap().immune_to =
+            vec![rules::CaptureImmunity::Piece("Pawn".into())];
+        assert_eq!(occupied.visible_squares(&white()), visible);
+    }
+
+    #[test]
+    fn fog_shows_a_shooters_range_while_it_is_empty() {
+        let mut game = fogged_game();
+        let mut archer = game.rules["Knight"].clone();
+        archer.name = "Archer".into();
+        archer.capabilities = vec![MovementCap::Shoot {
+            pattern: SlidePattern::FrontBack,
+            range: 3,
+            conditions: vec![],
+        }];
+        game.rules.insert(archer.name.clone(), archer);
+        put(&mut game, (6, 0), "Archer", white());
+
+        let visible = game.visible_squares(&white());
+        assert!(visible[3][6] && !visible[4][6]);
+    }
+
+    #[test]
+    fn view_hides_pieces_and_moves_in_the_fog() {
+        let mut game = fogged_game();
+        put(&mut game, (3, 5), "Knight", black());
+        put(&mut game, (2, 4), "Rook", black());
+        game.turn = black();
+        game.apply_move(&black(), (3, 5), (5, 6), None).unwrap();
+
+        let view = game.view_for(&white());
+        assert_eq!(piece_at(&view.state, (5, 6)), None);
+        assert_eq!(piece_at(&view.state, (7, 7)), None);
+        assert_eq!(piece_at(&view.state, (2, 4)), Some("Rook"));
+        assert_eq!(piece_at(&view.state, (3, 3)), Some("Pawn"));
+        assert!(view.state.last_move.is_none());
+
+        game.fog_of_war = false;
+        let view = game.view_for(&white());
+        assert!(view.visible.iter().flatten().all(|seen| *seen));
+        assert!(view.state.last_move.is_some());
+    }
+
+    #[test]
+    fn castling_cannot_cross_terrain_either_piece_could_not() {
+        let mut game = GameState::new(white(), black(), 10, 8, Grid::Square).unwrap();
+        for x in 1..9 {
+            if x != 5 {
+                game.board[0][x] = None;
+            }
+        }
+        game.set_terrain((1, 0), Terrain::Hole).unwrap();
+        game.set_terrain((2, 0), Terrain::Water).unwrap();
+        let moves = game.legal_moves_from((5, 0)).collect::<Vec<_>>();
+        assert!(!moves.contains(&(3, 0)) && moves.contains(&(7, 0)));
+        assert!(game.apply_move(&white(), (5, 0), (3, 0), None).is_err());
+
+        // the king's own path counts too
+        game.set_terrain((6, 0), Terrain::Water).unwrap();
+        assert!(!game.legal_moves_from((5, 0)).any(|to| to == (7, 0)));
+    }
+
+    #[test]
+    fn cannon_captures_over_exactly_one_screen() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        add_rule(
+            &mut game,
+            "Cannon",
+            vec![MovementCap::Hop {
+                pattern: SlidePattern::FrontBack,
+                range: 0,
+                land_behind_screen: false,
+                conditions: vec![],
+                mode: MoveMode::CaptureOnly,
+            }],
+        );
+        put(&mut game, (0, 0), "Cannon", white());
+        put(&mut game, (0, 6), "Rook", black());
+
+        // no screen
+        assert!(game.legal_moves_from((0, 0)).next().is_none());
+
+        put(&mut game, (0, 2), "Pawn", white());
+        assert!(game.legal_moves_from((0, 0)).eq([(0, 6)]));
+
+        // two screens, only the nearer one can be taken now
+        put(&mut game, (0, 4), "Pawn", black());
+        assert!(game.legal_moves_from((0, 0)).eq([(0, 4)]));
+        game.apply_move(&white(), (0, 0), (0, 4), None).unwrap();
+        assert_eq!(piece_at(&game, (0, 4)), Some("Cannon"));
+    }
+
+    #[test]
+    fn grasshopper_lands_right_behind_its_screen() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        let hop = |land_behind_screen| MovementCap::Hop {
+            pattern: SlidePattern::Omni,
+            range: 0,
+            land_behind_screen,
+            conditions: vec![],
+            mode: MoveMode::Both,
+        };
+        add_rule(&mut game, "Grasshopper", vec![hop(true)]);
+        put(&mut game, (1, 3), "Grasshopper", white());
+        put(&mut game, (1, 5), "Pawn", white());
+        put(&mut game, (3, 3), "Pawn", black());
+
+        let moves: Vec<_> = game.legal_moves_from((1, 3)).collect();
+        assert_eq!(moves, vec![(4, 3), (1, 6)]);
+
+        // without the flag it may land anywhere past the screen
+        game.rules.get_mut("Grasshopper").unwrap().capabilities = vec![hop(false)];
+        assert!(game.legal_moves_from((1, 3)).any(|to| to == (1, 7)));
+        assert!(game.legal_moves_from((1, 3)).any(|to| to == (5, 3)));
+    }
+
+    #[test]
+    fn rider_repeats_its_leap_until_blocked_or_out_of_range() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        let rider = |range| MovementCap::Rider {
+            possibilities: vec![(1, 2)],
+            range,
+            absolute: false,
+            conditions: vec![],
+            mode: MoveMode::Both,
+        };
+        add_rule(&mut game, "Nightrider", vec![rider(0)]);
+        put(&mut game, (0, 0), "Nightrider", white());
+        assert!(game.legal_moves_from((0, 0)).eq([(1, 2), (2, 4), (3, 6)]));
+
+        put(&mut game, (2, 4), "Pawn", black());
+        assert!(game.legal_moves_from((0, 0)).eq([(1, 2), (2, 4)]));
+
+        game.rules.get_mut("Nightrider").unwrap().capabilities = vec![rider(1)];
+        assert!(game.legal_moves_from((0, 0)).eq([(1, 2)]));
+    }
+
+    #[test]
+    fn rider_offsets_are_mirrored_for_the_second_player() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        let rider = |absolute| MovementCap::Rider {
+            possibilities: vec![(1, 2)],
+            range: 0,
+            absolute,
+            conditions: vec![],
+            mode: MoveMode::Both,
+        };
+        add_rule(&mut game, "Nightrider", vec![rider(false)]);
+        put(&mut game, (0, 7), "Nightrider", black());
+        assert!(game.legal_moves_from((0, 7)).eq([(3, 1), (2, 3), (1, 5)]));
+
+        // absolute offsets point the same way for both players, off the board here
+        game.rules.get_mut("Nightrider").unwrap().capabilities = vec![rider(true)];
+        assert!(game.legal_moves_from((0, 7)).next().is_none());
+    }
+
+    #[test]
+    fn custom_slide_directions_keep_their_own_range() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        add_rule(
+            &mut game,
+            "Lancer",
+            vec![MovementCap::Slide {
+                pattern: SlidePattern::Custom(vec![
+                    rules::SlideDirection {
+                        step: (1, 0),
+                        range: 2,
+                    },
+                    rules::SlideDirection {
+                        step: (0, 1),
+                        range: 0,
+                    },
+                ]),
+                range: 0,
+                can_jump: false,
+                only_forward: false,
+                conditions: vec![],
+                mode: MoveMode::Both,
+            }],
+        );
+        put(&mut game, (0, 3), "Lancer", white());
+        assert!(
+            game.legal_moves_from((0, 3))
+                .eq([(1, 3), (2, 3), (0, 4), (0, 5), (0, 6), (0, 7)])
+        );
+
+        // the second player's steps point down the board instead
+        put(&mut game, (7, 4), "Lancer", black());
+        assert!(
+            game.legal_moves_from((7, 4))
+                .eq([(7, 0), (7, 1), (7, 2), (7, 3)])
+        );
+        put(&mut game, (0, 4), "Lancer", black());
+        assert!(game.legal_moves_from((0, 4)).eq([(0, 3), (1, 4), (2, 4)]));
+    }
+
+    #[test]
+    fn slide_shorthands_match_their_custom_directions() {
+        let orthogonal = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+        let diagonal = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+        let custom = |steps: &[(i8, i8)]| {
+            SlidePattern::Custom(
+                steps
+                    .iter()
+                    .map(|&step| rules::SlideDirection { step, range: 0 })
+                    .collect(),
+            )
+        };
+        let cases = [
+            (SlidePattern::FrontBack, custom(&orthogonal)),
+            (SlidePattern::Diagonal, custom(&diagonal)),
+            (SlidePattern::Omni, custom(&[orthogonal, diagonal].concat())),
+        ];
+
+        for (shorthand, spelled_out) in cases {
+            let mut game = empty_game();
+            put(&mut game, (7, 0), "King", white());
+            put(&mut game, (7, 7), "King", black());
+            put(&mut game, (3, 5), "Pawn", black());
+            put(&mut game, (1, 1), "Pawn", white());
+            for (name, pattern) in [("Short", shorthand), ("Long", spelled_out)] {
+                add_rule(
+                    &mut game,
+                    name,
+                    vec![MovementCap::Slide {
+                        pattern,
+                        range: 0,
+                        can_jump: false,
+                        only_forward: false,
+                        conditions: vec![],
+                        mode: MoveMode::Both,
+                    }],
+                );
+            }
+            put(&mut game, (3, 3), "Short", white());
+            let short: Vec<_> = game.legal_moves_from((3, 3)).collect();
+            put(&mut game, (3, 3), "Long", white());
+            assert!(!short.is_empty());
+            assert!(game.legal_moves_from((3, 3)).eq(short));
+        }
+    }
+
+    #[test]
+    fn lame_leaps_are_blocked_by_the_squares_they_pass() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        let lame = |possibilities: Vec<(i8, i8)>| {
+            vec![MovementCap::Leap {
+                possibilities,
+                absolute: false,
+                lame: true,
+                conditions: vec![],
+                mode: MoveMode::Both,
+            }]
+        };
+        add_rule(
+            &mut game,
+            "Horse",
+            lame(vec![
+                (1, 2),
+                (2, 1),
+                (2, -1),
+                (1, -2),
+                (-1, -2),
+                (-2, -1),
+                (-2, 1),
+                (-1, 2),
+            ]),
+        );
+        add_rule(
+            &mut game,
+            "Elephant",
+            lame(vec![(2, 2), (2, -2), (-2, 2), (-2, -2)]),
+        );
+
+        // the horse steps straight first, so (3, 4) blocks both leaps that go up two
+        put(&mut game, (3, 3), "Horse", white());
+        assert!(game.legal_moves_from((3, 3)).any(|to| to == (2, 5)));
+        put(&mut game, (3, 4), "Pawn", black());
+        let moves: Vec<_> = game.legal_moves_from((3, 3)).collect();
+        assert!(!moves.contains(&(2, 5)) && !moves.contains(&(4, 5)));
+        assert!(moves.contains(&(5, 4)) && moves.contains(&(4, 1)));
+
+        // the elephant is stopped by whatever sits on the diagonal's midpoint
+        put(&mut game, (3, 3), "Elephant", white());
+        assert!(
+            game.legal_moves_from((3, 3))
+                .eq([(1, 1), (5, 1), (1, 5), (5, 5)])
+        );
+        put(&mut game, (4, 4), "Pawn", black());
+        assert!(game.legal_moves_from((3, 3)).eq([(1, 1), (5, 1), (1, 5)]));
+    }
+
+    fn add_archer(game: &mut GameState) {
+        add_rule(
+            game,
+            "Archer",
+            vec![MovementCap::Shoot {
+                pattern: SlidePattern::FrontBack,
+                range: 0,
+                conditions: vec![],
+            }],
+        );
+    }
+
+    #[test]
+    fn archer_shoots_without_moving_along_a_clear_line() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        add_archer(&mut game);
+        put(&mut game, (0, 0), "Archer", white());
+        put(&mut game, (2, 0), "Pawn", white());
+        put(&mut game, (0, 3), "Pawn", black());
+        put(&mut game, (0, 5), "Rook", black());
+
+        assert!(matches!(
+            game.apply_shot(&white(), (0, 0), (0, 5)),
+            Err(GameError::ViolatesRule(_))
+        ));
+        assert_eq!(
+            game.apply_shot(&white(), (0, 0), (2, 0)),
+            Err(GameError::DestinationOccupiedBySelf { x: 2, y: 0 })
+        );
+
+        game.apply_shot(&white(), (0, 0), (0, 3)).unwrap();
+        assert_eq!(piece_at(&game, (0, 0)), Some("Archer"));
+        assert_eq!(piece_at(&game, (0, 3)), None);
+        assert_eq!(game.last_move.as_ref().unwrap().kind, TurnKind::Shot);
+
+        // with the pawn gone the rook is in sight
+        game.apply_move(&black(), (4, 7), (3, 7), None).unwrap();
+        game.apply_shot(&white(), (0, 0), (0, 5)).unwrap();
+        assert_eq!(piece_at(&game, (0, 5)), None);
+    }
+
+    #[test]
+    fn shot_that_clears_the_line_gives_check() {
+        let mut game = empty_game();
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (0, 7), "King", black());
+        add_archer(&mut game);
+        put(&mut game, (0, 0), "Archer", white());
+        put(&mut game, (0, 4), "Pawn", black());
+        assert!(!game.is_in_check(&black()));
+
+        game.apply_shot(&white(), (0, 0), (0, 4)).unwrap();
+        assert!(game.is_in_check(&black()));
+        assert_eq!(game.check_game_over(), None);
+        assert_eq!(
+            game.apply_move(&black(), (0, 7), (0, 6), None),
+            Err(GameError::LeavesRoyalInCheck)
+        );
+        assert!(game.apply_move(&black(), (0, 7), (1, 7), None).is_ok());
+    }
+
+    /// a white rook-like bomber on (0, 0) lined up with a black pawn on (0, 5)
+    fn blast_game(immune: Vec<String>, self_destruct: bool) -> GameState {
+        let mut game = empty_game();
+        put(&mut game, (7, 0), "King", white());
+        put(&mut game, (7, 7), "King", black());
+        let mut bomber = game.rules["Rook"].clone();
+        bomber.name = "Bomber".into();
+        bomber.on_capture = vec![rules::CaptureEffect::Explode {
+            radius: 1,
+            immune,
+            self_destruct,
+        }];
+        game.rules.insert(bomber.name.clone(), bomber);
+        put(&mut game, (0, 0), "Bomber", white());
+        put(&mut game, (0, 5), "Pawn", black());
+        game
+    }
+
+    #[test]
+    fn capture_blast_clears_the_radius_except_immune_pieces() {
+        let mut game = blast_game(vec!["Knight".into()], false);
+        put(&mut game, (1, 5), "Pawn", black());
+        put(&mut game, (0, 6), "Pawn", white());
+        put(&mut game, (1, 4), "Knight", black());
+        put(&mut game, (2, 5), "Rook", black());
+
+        game.apply_move(&white(), (0, 0), (0, 5), None).unwrap();
+        assert_eq!(piece_at(&game, (0, 5)), Some("Bomber"));
+        assert_eq!(piece_at(&game, (1, 5)), None);
+        assert_eq!(piece_at(&game, (0, 6)), None);
+        assert_eq!(piece_at(&game, (1, 4)), Some("Knight"));
+        assert_eq!(piece_at(&game, (2, 5)), Some("Rook"));
+    }
+
+    #[test]
+    fn self_destructing_capturer_goes_down_with_the_blast() {
+        let mut game = blast_game(vec![], true);
+        game.apply_move(&white(), (0, 0), (0, 5), None).unwrap();
+        assert_eq!(piece_at(&game, (0, 5)), None);
+        assert_eq!(piece_at(&game, (0, 0)), None);
+    }
+
+    #[test]
+    fn blast_cannot_take_out_your_own_royal() {
+        let mut game = blast_game(vec![], false);
+        game.board[0][7] = None;
+        put(&mut game, (1, 6), "King", white());
+
+        assert_eq!(
+            game.apply_move(&white(), (0, 0), (0, 5), None),
+            Err(GameError::LeavesRoyalInCheck)
+        );
+        assert_eq!(piece_at(&game, (0, 0)), Some("Bomber"));
+        assert_eq!(piece_at(&game, (0, 5)), Some("Pawn"));
+    }
+
+    #[test]
+    fn push_needs_an_empty_square_on_the_board_behind_the_target() {
+        let mut game = empty_game();
+        put(&mut game, (7, 0), "King", white());
+        put(&mut game, (0, 7), "King", black());
+        add_rule(
+            &mut game,
+            "Ram",
+            vec![MovementCap::Slide {
+                pattern: SlidePattern::FrontBack,
+                range: 1,
+                can_jump: false,
+                only_forward: false,
+                conditions: vec![],
+                mode: MoveMode::Push,
+            }],
+        );
+        put(&mut game, (3, 2), "Ram", white());
+        put(&mut game, (3, 3), "Pawn", black());
+        put(&mut game, (3, 6), "Ram", white());
+        put(&mut game, (3, 7), "Pawn", black());
+
+        // off the far edge
+        assert!(game.apply_move(&white(), (3, 6), (3, 7), None).is_err());
+
+        // into a piece or a hole
+        put(&mut game, (3, 4), "Pawn", black());
+        assert!(game.apply_move(&white(), (3, 2), (3, 3), None).is_err());
+        game.board[4][3] = None;
+        game.set_terrain((3, 4), Terrain::Hole).unwrap();
+        assert!(game.apply_move(&white(), (3, 2), (3, 3), None).is_err());
+
+        game.set_terrain((3, 4), Terrain::Plain).unwrap();
+        game.apply_move(&white(), (3, 2), (3, 3), None).unwrap();
+        assert_eq!(piece_at(&game, (3, 3)), Some("Ram"));
+        assert_eq!(piece_at(&game, (3, 4)), Some("Pawn"));
+        assert_eq!(piece_at(&game, (3, 2)), None);
+    }
+
+    #[test]
+    fn swap_needs_ground_the_partner_can_stand_on() {
+        let mut game = empty_game();
+        put(&mut game, (7, 0), "King", white());
+        put(&mut game, (0, 7), "King", black());
+        add_rule(
+            &mut game,
+            "Squire",
+            vec![MovementCap::Slide {
+                pattern: SlidePattern::Omni,
+                range: 1,
+                can_jump: false,
+                only_forward: false,
+                conditions: vec![],
+                mode: MoveMode::Swap,
+            }],
+        );
+        game.rules.get_mut("Squire").unwrap().terrain = vec![Terrain::Water];
+        put(&mut game, (2, 2), "Squire", white());
+        put(&mut game, (3, 3), "Pawn", white());
+        put(&mut game, (5, 5), "Squire", white());
+        put(&mut game, (4, 4), "Pawn", white());
+        game.set_terrain((5, 5), Terrain::Water).unwrap();
+
+        // the pawn can't swim, so it can't take the squire's place
+        assert!(game.apply_move(&white(), (5, 5), (4, 4), None).is_err());
+
+        game.apply_move(&white(), (2, 2), (3, 3), None).unwrap();
+        assert_eq!(piece_at(&game, (3, 3)), Some("Squire"));
+        assert_eq!(piece_at(&game, (2, 2)), Some("Pawn"));
+    }
+
+    #[test]
+    fn immune_pieces_refuse_only_the_capturers_they_name() {
+        use rules::CaptureImmunity;
+
+        let game_with = |immunity: CaptureImmunity| {
+            let mut game = empty_game();
+            put(&mut game, (7, 0), "King", white());
+            put(&mut game, (7, 7), "King", black());
+            add_rule(&mut game, "Target", vec![]);
+            game.rules.get_mut("Target").unwrap().immune_to = vec![immunity];
+            put(&mut game, (0, 5), "Target", black());
+            put(&mut game, (7, 1), "Target", black());
+            put(&mut game, (0, 0), "Rook", white());
+            put(&mut game, (1, 3), "Knight", white());
+            game
+        };
+        let immune = |capturer: &str| GameError::ImmuneToCapture {
+            piece: "Target".into(),
+            capturer: capturer.into(),
+        };
+
+        let mut game = game_with(CaptureImmunity::Piece("Rook".into()));
+        assert_eq!(
+            game.apply_move(&white(), (0, 0), (0, 5), None),
+            Err(immune("Rook"))
+        );
+        assert!(game.apply_move(&white(), (1, 3), (0, 5), None).is_ok());
+
+        let mut game = game_with(CaptureImmunity::Leapers);
+        assert_eq!(
+            game.apply_move(&white(), (1, 3), (0, 5), None),
+            Err(immune("Knight"))
+        );
+        assert!(game.apply_move(&white(), (0, 0), (0, 5), None).is_ok());
+
+        let mut game = game_with(CaptureImmunity::Royals);
+        assert_eq!(
+            game.apply_move(&white(), (7, 0), (7, 1), None),
+            Err(immune("King"))
+        );
+        assert!(game.apply_move(&white(), (0, 0), (0, 5), None).is_ok());
+    }
+
+    #[test]
+    fn protector_shields_its_neighbours_but_not_itself() {
+        let mut game = empty_game();
+        put(&mut game, (7, 0), "King", white());
+        put(&mut game, (7, 7), "King", black());
+        add_rule(&mut game, "Guard", vec![]);
+        game.rules.get_mut("Guard").unwrap().protects_adjacent = true;
+        put(&mut game, (0, 5), "Pawn", black());
+        put(&mut game, (1, 6), "Guard", black());
+        put(&mut game, (0, 0), "Rook", white());
+        put(&mut game, (1, 0), "Rook", white());
+
+        assert_eq!(
+            game.apply_move(&white(), (0, 0), (0, 5), None),
+            Err(GameError::ProtectedPiece {
+                x: 0,
+                y: 5,
+                protector: "Guard".into(),
+            })
+        );
+        game.apply_move(&white(), (1, 0), (1, 6), None).unwrap();
+
+        // with the guard gone the pawn is fair game
+        game.apply_move(&black(), (7, 7), (6, 7), None).unwrap();
+        assert!(game.apply_move(&white(), (0, 0), (0, 5), None).is_ok());
+    }
+
+    #[test]
+    fn hex_distances_and_orientation() {
+        assert_eq!(Grid::Square.length((1, 1)), 1);
+        assert_eq!(Grid::Hex.length((1, 1)), 2);
+        assert_eq!(Grid::Hex.length((1, -1)), 1);
+        assert_eq!(Grid::Hex.length((2, -3)), 3);
+
+        // a mirror on squares, a half turn on hexes
+        assert_eq!(Grid::Square.orient((1, 2), -1), (1, -2));
+        assert_eq!(Grid::Hex.orient((1, 2), -1), (-1, -2));
+        assert_eq!(Grid::Hex.orient((1, 2), 1), (1, 2));
+    }
+
+    #[test]
+    fn hex_back_ranks_are_turned_round_for_the_second_player() {
+        let game = GameState::new(white(), black(), 8, 8, Grid::Hex).unwrap();
+        for x in 0..8 {
+            assert_eq!(piece_at(&game, (x, 0)), piece_at(&game, (7 - x, 7)));
+        }
+        assert_eq!(piece_at(&game, (4, 0)), Some("King"));
+        assert_eq!(piece_at(&game, (3, 7)), Some("King"));
+    }
+
+    #[test]
+    fn hex_rook_slides_along_six_lines() {
+        let mut game = GameState::new(white(), black(), 8, 8, Grid::Hex).unwrap();
+        game.board = vec![vec![None; 8]; 8];
+        put(&mut game, (0, 0), "King", white());
+        put(&mut game, (7, 7), "King", black());
+        put(&mut game, (3, 3), "Rook", white());
+
+        let moves: Vec<_> = game.legal_moves_from((3, 3)).collect();
+        assert_eq!(moves.len(), 20);
+        assert!(moves.contains(&(6, 0)) && moves.contains(&(0, 6)));
+        assert!(!moves.contains(&(4, 4)) && !moves.contains(&(2, 2)));
+    }
+
+    #[test]
+    fn lame_leaps_are_refused_on_hex_boards() {
+        let game = empty_game();
+        let mut rule = game.rules["Knight"].clone();
+        rule.capabilities = vec![MovementCap::Leap {
+            possibilities: vec![(1, 2)],
+            absolute: false,
+            lame: true,
+            conditions: vec![],
+            mode: MoveMode::Both,
+        }];
+        assert!(rule.validate(Grid::Square).is_ok());
+        assert!(matches!(
+            rule.validate(Grid::Hex),
+            Err(GameError::ViolatesRule(_))
+        ));
+    }
+
+    #[test]
+    fn captured_pieces_go_to_the_captors_hand() {
+        let mut game = empty_game();
+        game.drops = true;
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        put(&mut game, (0, 0), "Rook", white());
+        put(&mut game, (0, 5), "Knight", black());
+
+        game.apply_move(&white(), (0, 0), (0, 5), None).unwrap();
+        assert_eq!(
+            game.hand(&white()).to_vec(),
+            vec![PieceType("Knight".into())]
+        );
+        assert!(game.hand(&black()).is_empty());
+    }
+
+    #[test]
+    fn drops_go_on_empty_squares_outside_forced_promotion() {
+        let mut game = empty_game();
+        game.drops = true;
+        put(&mut game, (4, 0), "King", white());
+        put(&mut game, (4, 7), "King", black());
+        game.hands.0 = vec![PieceType("Pawn".into())];
+
+        assert!(matches!(
+            game.apply_drop(&white(), "Pawn", (4, 0)),
+            Err(GameError::ViolatesRule(_))
+        ));
+        assert!(matches!(
+            game.apply_drop(&white(), "Pawn", (2, 7)),
+            Err(GameError::ViolatesRule(_))
+        ));
+        assert_eq!(
+            game.apply_drop(&white(), "Knight", (2, 5)),
+            Err(GameError::NotInHand("Knight".into()))
+        );
+
+        game.apply_drop(&white(), "Pawn", (2, 5)).unwrap();
+        assert_eq!(piece_at(&game, (2, 5)), Some("Pawn"));
+        assert!(game.hand(&white()).is_empty());
+        assert_eq!(game.last_move.as_ref().unwrap().kind, TurnKind::Drop);
+    }
+
+    #[test]
+    fn drop_has_to_answer_a_check() {
+        let mut game = empty_game();
+        game.drops = true;
+        put(&mut game, (0, 0), "King", white());
+        put(&mut game, (7, 7), "King", black());
+        put(&mut game, (0, 7), "Rook", black());
+        game.hands.0 = vec![PieceType("Knight".into())];
+        assert!(game.is_in_check(&white()));
+
+        assert_eq!(
+            game.apply_drop(&white(), "Knight", (5, 5)),
+            Err(GameError::LeavesRoyalInCheck)
+        );
+        game.apply_drop(&white(), "Knight", (0, 4)).unwrap();
+        assert!(!game.is_in_check(&white()));
+    }
 }
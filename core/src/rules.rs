@@ -1,9 +1,15 @@
 use serde::{Deserialize, Serialize};
 
+use crate::{GameState, Grid, Piece, Terrain, errors::GameError};
+
 /// designing JSON format
-/// a piece can move in 2 ways ,
-/// Slide it , jump it
-/// slide is more like a pawn move , jump is more knight move
+/// a piece can move in a few ways ,
+/// Slide it , hop it over a screen , ride it , jump it
+/// slide is more like a pawn move , hop is the xiangqi cannon ,
+/// ride is a knight that keeps going , jump is more knight move
+/// compound moves two pieces at once , like castling
+/// shoot takes a piece from afar without moving at all
+/// the mode on a capability can also let it swap with a friend or push a piece aside
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MovementCap {
     #[serde(rename = "slide")]
@@ -12,9 +18,153 @@ pub enum MovementCap {
         range: u8,
         can_jump: bool,
         only_forward: bool,
+        #[serde(default)]
+        conditions: Vec<MoveCondition>,
+        #[serde(default)]
+        mode: MoveMode,
+    },
+    /// cannon / grasshopper: moves along a pattern over exactly one piece (the screen)
+    #[serde(rename = "hop")]
+    Hop {
+        pattern: SlidePattern,
+        range: u8,
+        /// grasshopper style, has to land on the square right after the screen
+        #[serde(default)]
+        land_behind_screen: bool,
+        #[serde(default)]
+        conditions: Vec<MoveCondition>,
+        #[serde(default)]
+        mode: MoveMode,
+    },
+    /// nightrider: repeats a leap along the same vector until something is in the way
+    #[serde(rename = "rider")]
+    Rider {
+        possibilities: Vec<(i8, i8)>,
+        range: u8, // max number of leaps, 0 = infinite
+        #[serde(default)]
+        absolute: bool,
+        #[serde(default)]
+        conditions: Vec<MoveCondition>,
+        #[serde(default)]
+        mode: MoveMode,
     },
+    /// offsets are from the first player's side (+y is forward) and mirrored for the second,
+    /// unless `absolute` is set
     #[serde(rename = "leap")]
-    Leap { possibilities: Vec<(i8, i8)> },
+    Leap {
+        possibilities: Vec<(i8, i8)>,
+        #[serde(default)]
+        absolute: bool,
+        /// xiangqi horse / elephant: the leap is blocked by anything on the squares it passes,
+        /// straight along the longer axis first, then diagonally
+        #[serde(default)]
+        lame: bool,
+        #[serde(default)]
+        conditions: Vec<MoveCondition>,
+        #[serde(default)]
+        mode: MoveMode,
+    },
+    /// archer: takes an enemy in line of sight along `pattern` without moving
+    #[serde(rename = "shoot")]
+    Shoot {
+        pattern: SlidePattern,
+        range: u8, // 0 = infinite
+        #[serde(default)]
+        conditions: Vec<MoveCondition>,
+    },
+    /// castling: moves by `offset` and relocates a friendly `partner` in the same turn.
+    /// partner squares are relative to this piece's start, conditions apply to both pieces
+    #[serde(rename = "compound")]
+    Compound {
+        offset: (i8, i8),
+        partner: String,
+        partner_from: (i8, i8),
+        partner_to: (i8, i8),
+        /// none of the squares this piece starts on, passes or lands on may be attacked
+        #[serde(default)]
+        safe_path: bool,
+        #[serde(default)]
+        conditions: Vec<MoveCondition>,
+    },
+}
+
+impl MovementCap {
+    /// every condition has to hold for the capability to be usable
+    pub fn conditions(&self) -> &[MoveCondition] {
+        match self {
+            MovementCap::Slide { conditions, .. }
+            | MovementCap::Hop { conditions, .. }
+            | MovementCap::Rider { conditions, .. }
+            | MovementCap::Leap { conditions, .. }
+            | MovementCap::Shoot { conditions, .. }
+            | MovementCap::Compound { conditions, .. } => conditions,
+        }
+    }
+
+    pub fn mode(&self) -> MoveMode {
+        match self {
+            MovementCap::Slide { mode, .. }
+            | MovementCap::Hop { mode, .. }
+            | MovementCap::Rider { mode, .. }
+            | MovementCap::Leap { mode, .. } => *mode,
+            MovementCap::Shoot { .. } => MoveMode::CaptureOnly,
+            // the pieces land on empty squares
+            MovementCap::Compound { .. } => MoveMode::MoveOnly,
+        }
+    }
+}
+
+/// whether a capability can be used to move to an empty square, to capture, or both
+/// chess pawn moves straight ahead but captures diagonally
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MoveMode {
+    #[default]
+    #[serde(rename = "both")]
+    Both,
+    #[serde(rename = "move_only")]
+    MoveOnly,
+    #[serde(rename = "capture_only")]
+    CaptureOnly,
+    /// moves to an empty square the opponent's last moved piece passed over and takes it,
+    /// only works against pieces of the same kind
+    #[serde(rename = "en_passant")]
+    EnPassant,
+    /// moves onto a friendly piece and the two trade places
+    #[serde(rename = "swap")]
+    Swap,
+    /// moves onto an occupied square and shoves whatever is there one square further,
+    /// never captures
+    #[serde(rename = "push")]
+    Push,
+}
+
+impl MoveMode {
+    /// `is_capture` means an enemy is on the target, `is_friendly` one of our own
+    pub fn allows(&self, is_capture: bool, is_friendly: bool) -> bool {
+        match self {
+            MoveMode::Both => !is_friendly,
+            MoveMode::MoveOnly | MoveMode::EnPassant => !is_capture && !is_friendly,
+            MoveMode::CaptureOnly => is_capture,
+            MoveMode::Swap => is_friendly,
+            MoveMode::Push => is_capture || is_friendly,
+        }
+    }
+}
+
+/// restricts when a capability can be used, e.g. a pawn's double step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MoveCondition {
+    /// only if the piece has never moved
+    #[serde(rename = "first_move")]
+    FirstMove,
+}
+
+impl MoveCondition {
+    pub fn is_met(&self, piece: &Piece) -> bool {
+        match self {
+            MoveCondition::FirstMove => piece.move_count == 0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +175,62 @@ pub enum SlidePattern {
     Diagonal,
     #[serde(rename = "omni")]
     Omni,
+    /// explicit directions, e.g. shogi silver general or a sideways-only rook
+    #[serde(rename = "custom")]
+    Custom(Vec<SlideDirection>),
+}
+
+/// one direction of a slide
+/// `step` is written from the first player's side, +y is forward, and gets mirrored for the second player
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlideDirection {
+    pub step: (i8, i8),
+    /// 0 = only limited by the slide's range
+    #[serde(default)]
+    pub range: u8,
+}
+
+const ORTHOGONAL: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const DIAGONAL: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+// axial hex steps, the 6 neighbours and the 6 cells straight past a corner
+const HEX_ORTHOGONAL: [(i8, i8); 6] = [(0, 1), (1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1)];
+const HEX_DIAGONAL: [(i8, i8); 6] = [(1, 1), (2, -1), (1, -2), (-1, -1), (-2, 1), (-1, 2)];
+
+/// the 12 cells a hex knight jumps to, an orthogonal step followed by a diagonal one
+pub const HEX_KNIGHT: [(i8, i8); 12] = [
+    (1, 2),
+    (2, 1),
+    (3, -1),
+    (3, -2),
+    (2, -3),
+    (1, -3),
+    (-1, -2),
+    (-2, -1),
+    (-3, 1),
+    (-3, 2),
+    (-2, 3),
+    (-1, 3),
+];
+
+impl SlidePattern {
+    /// the shorthands are just named direction sets, with six of each on a hex grid
+    pub fn directions(&self, grid: Grid) -> Vec<SlideDirection> {
+        let (orthogonal, diagonal) = match grid {
+            Grid::Square => (ORTHOGONAL.as_slice(), DIAGONAL.as_slice()),
+            Grid::Hex => (HEX_ORTHOGONAL.as_slice(), HEX_DIAGONAL.as_slice()),
+        };
+        let steps: Vec<(i8, i8)> = match self {
+            SlidePattern::FrontBack => orthogonal.to_vec(),
+            SlidePattern::Diagonal => diagonal.to_vec(),
+            SlidePattern::Omni => orthogonal.iter().chain(diagonal.iter()).copied().collect(),
+            SlidePattern::Custom(directions) => return directions.clone(),
+        };
+        steps
+            .into_iter()
+            .map(|step| SlideDirection { step, range: 0 })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -35,4 +241,178 @@ pub struct PieceRule {
 
     #[serde(default)]
     pub is_royal: bool,
+
+    #[serde(default)]
+    pub promotion: Option<Promotion>,
+
+    /// what happens after this piece captures something
+    #[serde(default)]
+    pub on_capture: Vec<CaptureEffect>,
+
+    /// pieces that can never capture this one
+    #[serde(default)]
+    pub immune_to: Vec<CaptureImmunity>,
+
+    /// friendly pieces on the 8 squares around this one can't be captured
+    #[serde(default)]
+    pub protects_adjacent: bool,
+
+    /// terrain this piece is at home in, water it can enter, forest it can slide through
+    #[serde(default)]
+    pub terrain: Vec<Terrain>,
+
+    /// what it costs to bring a new one onto the board and where it may appear
+    #[serde(default)]
+    pub spawn: Spawn,
+
+    /// how much punishment it takes and deals, captures only land once hit points run out
+    #[serde(default)]
+    pub combat: Combat,
+
+    /// own turns the piece has to sit out after it moves or shoots, 0 = none
+    #[serde(default)]
+    pub cooldown: u32,
+
+    /// how many times the piece may move or shoot in the whole game, unlimited if unset
+    #[serde(default)]
+    pub max_moves: Option<u32>,
+}
+
+impl PieceRule {
+    /// Checks a proposed rule before it reaches a game on `grid`. Offsets have to fit on the
+    /// largest board, anything bigger could overflow when flipped for the second player,
+    /// a piece with no hit points or no attack would break captures, and lame leaps
+    /// only know the path across squares
+    pub fn validate(&self, grid: Grid) -> Result<(), GameError> {
+        if self.combat.hit_points == 0 || self.combat.attack == 0 {
+            return Err(GameError::ViolatesRule(
+                "Hit points and attack have to be at least 1".into(),
+            ));
+        }
+
+        let limit = GameState::MAX_BOARD_SIZE as i8 - 1;
+        let fits = |(x, y): &(i8, i8)| (-limit..=limit).contains(x) && (-limit..=limit).contains(y);
+        let steps = |pattern: &SlidePattern| match pattern {
+            SlidePattern::Custom(directions) => directions.iter().map(|d| d.step).collect(),
+            _ => Vec::new(),
+        };
+
+        for cap in &self.capabilities {
+            if grid == Grid::Hex && matches!(cap, MovementCap::Leap { lame: true, .. }) {
+                return Err(GameError::ViolatesRule(
+                    "Lame leaps only work on a square grid".into(),
+                ));
+            }
+
+            let offsets: Vec<(i8, i8)> = match cap {
+                MovementCap::Slide { pattern, .. }
+                | MovementCap::Hop { pattern, .. }
+                | MovementCap::Shoot { pattern, .. } => steps(pattern),
+                MovementCap::Rider { possibilities, .. }
+                | MovementCap::Leap { possibilities, .. } => possibilities.clone(),
+                MovementCap::Compound {
+                    offset,
+                    partner_from,
+                    partner_to,
+                    ..
+                } => vec![*offset, *partner_from, *partner_to],
+            };
+            if let Some(offset) = offsets.iter().find(|offset| !fits(offset)) {
+                return Err(GameError::ViolatesRule(format!(
+                    "Offset {:?} is larger than any board, keep it within {}",
+                    offset, limit
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// who is not allowed to take a piece
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptureImmunity {
+    /// a single piece type, by rule name
+    #[serde(rename = "piece")]
+    Piece(String),
+    /// anything with a leap or rider capability, knights and friends
+    #[serde(rename = "leapers")]
+    Leapers,
+    /// royal pieces
+    #[serde(rename = "royals")]
+    Royals,
+}
+
+impl CaptureImmunity {
+    pub fn covers(&self, capturer: &Piece, capturer_rule: &PieceRule) -> bool {
+        match self {
+            CaptureImmunity::Piece(name) => capturer.piece_type.0 == *name,
+            CaptureImmunity::Leapers => capturer_rule
+                .capabilities
+                .iter()
+                .any(|cap| matches!(cap, MovementCap::Leap { .. } | MovementCap::Rider { .. })),
+            CaptureImmunity::Royals => capturer_rule.is_royal,
+        }
+    }
+}
+
+/// runs after the capture itself, in order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptureEffect {
+    /// atomic chess: everything within `radius` of the captured square is removed
+    #[serde(rename = "explode")]
+    Explode {
+        radius: u8, // 1 = the 8 neighbours
+        /// piece names that survive the blast
+        #[serde(default)]
+        immune: Vec<String>,
+        /// the capturing piece goes down with it
+        #[serde(default)]
+        self_destruct: bool,
+    },
+}
+
+/// what a piece can turn into once it reaches the far side of the board
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Promotion {
+    /// how deep the zone is, counted from the owner's far edge, 1 = last rank only
+    pub ranks: u8,
+    /// names of the rules the piece may become
+    pub into: Vec<String>,
+    /// if set the piece has to promote when it lands in the zone (chess pawn)
+    #[serde(default)]
+    pub mandatory: bool,
+}
+
+/// how a piece gets spawned, paid for out of the owner's spawn points
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Spawn {
+    /// points taken from the owner's budget
+    pub cost: u32,
+    /// how deep the zone is, counted from the owner's home edge, 1 = back rank only
+    pub ranks: u8,
+}
+
+impl Default for Spawn {
+    fn default() -> Self {
+        Self { cost: 1, ranks: 2 }
+    }
+}
+
+/// hit points and attack strength, the default of one each is plain chess
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Combat {
+    /// damage the piece can take, it is captured by the hit that uses up the last one
+    pub hit_points: u32,
+    /// damage dealt to whatever the piece attacks
+    pub attack: u32,
+}
+
+impl Default for Combat {
+    fn default() -> Self {
+        Self {
+            hit_points: 1,
+            attack: 1,
+        }
+    }
 }
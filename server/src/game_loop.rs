@@ -1,5 +1,6 @@
 use core::GameState;
-use core::{Board, PlayerId, errors::GameError};
+use core::{Board, Grid, PlayerId, Terrain, Topology, errors::GameError};
+use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, mpsc, oneshot};
 use uuid::Uuid;
 
@@ -8,6 +9,19 @@ pub enum Command {
         player_id: String,
         from: (u8, u8),
         to: (u8, u8),
+        promotion: Option<String>,
+        response: oneshot::Sender<Result<(), GameError>>,
+    },
+    Shoot {
+        player_id: String,
+        from: (u8, u8),
+        target: (u8, u8),
+        response: oneshot::Sender<Result<(), GameError>>,
+    },
+    Drop {
+        player_id: String,
+        piece_type: String,
+        at: (u8, u8),
         response: oneshot::Sender<Result<(), GameError>>,
     },
     GetState {
@@ -15,6 +29,9 @@ pub enum Command {
     },
     Join {
         player_name: String,
+        setup: GameSetup,
+        /// where this player's own messages go, e.g. their fog of war view
+        outbox: mpsc::Sender<String>,
         response: oneshot::Sender<Result<PlayerId, JoinError>>,
     },
     ProposeRule {
@@ -53,11 +70,69 @@ pub enum GameStatus {
     Finished { winner: Option<PlayerId> },
 }
 
+/// How the first player to join wants the game set up
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameSetup {
+    /// (width, height), 8x8 if left out
+    #[serde(default)]
+    pub board_size: Option<(u8, u8)>,
+    #[serde(default)]
+    pub topology: Topology,
+    #[serde(default)]
+    pub grid: Grid,
+    /// squares that start out as holes, water or forest
+    #[serde(default)]
+    pub terrain: Vec<((u8, u8), Terrain)>,
+    /// each player only sees what their own pieces can reach
+    #[serde(default)]
+    pub fog_of_war: bool,
+    /// captured pieces go to the captor's hand and can be dropped back in
+    #[serde(default)]
+    pub drops: bool,
+    /// points each player can spend on spawning, GameState::DEFAULT_SPAWN_POINTS if left out
+    #[serde(default)]
+    pub spawn_points: Option<u32>,
+}
+
+impl GameSetup {
+    fn size(&self) -> (u8, u8) {
+        self.board_size.unwrap_or((8, 8))
+    }
+
+    fn validate(&self) -> Result<(), JoinError> {
+        let (width, height) = self.size();
+        // try the setup on a fresh game, so nothing gets placed under a starting piece
+        let mut trial = GameState::new(
+            PlayerId("first".into()),
+            PlayerId("second".into()),
+            width,
+            height,
+            self.grid,
+        )
+        .map_err(|_| JoinError::InvalidBoardSize)?;
+        trial.set_topology(self.topology);
+        for (square, terrain) in &self.terrain {
+            trial
+                .set_terrain(*square, *terrain)
+                .map_err(|_| JoinError::InvalidTerrain)?;
+        }
+        // a short torus still has the kings touching across the wrapped edge
+        let (first, second) = trial.players.clone();
+        if trial.is_in_check(&first) || trial.is_in_check(&second) {
+            return Err(JoinError::RoyalInCheck);
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub enum JoinError {
     GameFull,
     NameTaken,
     GameAlreadyStarted,
+    InvalidBoardSize,
+    InvalidTerrain,
+    RoyalInCheck,
 }
 
 pub struct GameLoop {
@@ -67,6 +142,8 @@ pub struct GameLoop {
     players: Vec<(String, PlayerId)>,
     status: GameStatus,
     pending_proposal: Option<(String, core::rules::PieceRule)>,
+    setup: GameSetup, // picked by the first player, used once the second one joins
+    outboxes: Vec<(PlayerId, mpsc::Sender<String>)>,
 }
 
 impl GameLoop {
@@ -78,6 +155,8 @@ impl GameLoop {
             players: Vec::new(),
             status: GameStatus::WaitingForPlayers,
             pending_proposal: None,
+            setup: GameSetup::default(),
+            outboxes: Vec::new(),
         }
     }
 
@@ -88,9 +167,28 @@ impl GameLoop {
                     player_id,
                     from,
                     to,
+                    promotion,
+                    response,
+                } => {
+                    let result = self.handle_move(&player_id, from, to, promotion).await;
+                    let _ = response.send(result);
+                }
+                Command::Shoot {
+                    player_id,
+                    from,
+                    target,
+                    response,
+                } => {
+                    let result = self.handle_shoot(&player_id, from, target).await;
+                    let _ = response.send(result);
+                }
+                Command::Drop {
+                    player_id,
+                    piece_type,
+                    at,
                     response,
                 } => {
-                    let result = self.handle_move(&player_id, from, to).await;
+                    let result = self.handle_drop(&player_id, &piece_type, at).await;
                     let _ = response.send(result);
                 }
                 Command::GetState { response } => {
@@ -99,9 +197,11 @@ impl GameLoop {
                 }
                 Command::Join {
                     player_name,
+                    setup,
+                    outbox,
                     response,
                 } => {
-                    let result = self.handle_join(player_name).await;
+                    let result = self.handle_join(player_name, setup, outbox).await;
                     let _ = response.send(result);
                 }
                 Command::ProposeRule {
@@ -110,7 +210,7 @@ impl GameLoop {
                     response,
                 } => {
                     let result = self.handle_propose_rule(player_id, rule).await;
-                    response.send(result);
+                    let _ = response.send(result);
                 }
                 Command::SpawnPiece {
                     player_id,
@@ -118,20 +218,8 @@ impl GameLoop {
                     position,
                     response,
                 } => {
-                    if let Some(game) = &mut self.game {
-                        if !game.rules.contains_key(&piece_name) {
-                            let _ = response
-                                .send(Err(GameError::ViolatesRule("Rule doest not exist".into())));
-                        } else {
-                            game.board[position.1 as usize][position.0 as usize] =
-                                Some(core::Piece {
-                                    piece_type: core::PieceType(piece_name),
-                                    owner: core::PlayerId(player_id),
-                                });
-                            self.broadcast_state();
-                            let _ = response.send(Ok(()));
-                        }
-                    }
+                    let result = self.handle_spawn(&player_id, &piece_name, position).await;
+                    let _ = response.send(result);
                 }
                 Command::CastVote {
                     player_id,
@@ -150,6 +238,28 @@ impl GameLoop {
         player_id: &str,
         from: (u8, u8),
         to: (u8, u8),
+        promotion: Option<String>,
+    ) -> Result<(), GameError> {
+        let game = self.game.as_mut().ok_or(GameError::GameNotStarted)?;
+
+        let pid = self
+            .players
+            .iter()
+            .find(|(_, id)| id.0 == player_id)
+            .map(|(_, id)| id)
+            .ok_or(GameError::InvalidPlayer)?;
+
+        game.apply_move(pid, from, to, promotion)?;
+
+        self.finish_turn();
+        Ok(())
+    }
+
+    async fn handle_shoot(
+        &mut self,
+        player_id: &str,
+        from: (u8, u8),
+        target: (u8, u8),
     ) -> Result<(), GameError> {
         let game = self.game.as_mut().ok_or(GameError::GameNotStarted)?;
 
@@ -160,24 +270,73 @@ impl GameLoop {
             .map(|(_, id)| id)
             .ok_or(GameError::InvalidPlayer)?;
 
-        game.apply_move(pid, from, to)?;
+        game.apply_shot(pid, from, target)?;
 
-        if let Some(winner) = game.check_game_over() {
+        self.finish_turn();
+        Ok(())
+    }
+
+    async fn handle_drop(
+        &mut self,
+        player_id: &str,
+        piece_type: &str,
+        at: (u8, u8),
+    ) -> Result<(), GameError> {
+        let game = self.game.as_mut().ok_or(GameError::GameNotStarted)?;
+
+        let pid = self
+            .players
+            .iter()
+            .find(|(_, id)| id.0 == player_id)
+            .map(|(_, id)| id)
+            .ok_or(GameError::InvalidPlayer)?;
+
+        game.apply_drop(pid, piece_type, at)?;
+
+        self.finish_turn();
+        Ok(())
+    }
+
+    async fn handle_spawn(
+        &mut self,
+        player_id: &str,
+        piece_name: &str,
+        position: (u8, u8),
+    ) -> Result<(), GameError> {
+        let game = self.game.as_mut().ok_or(GameError::GameNotStarted)?;
+
+        let pid = self
+            .players
+            .iter()
+            .find(|(_, id)| id.0 == player_id)
+            .map(|(_, id)| id)
+            .ok_or(GameError::InvalidPlayer)?;
+
+        game.apply_spawn(pid, piece_name, position)?;
+
+        self.finish_turn();
+        Ok(())
+    }
+
+    /// game over check and state broadcast after any turn taking action
+    fn finish_turn(&mut self) {
+        let Some(game) = &self.game else {
+            return;
+        };
+
+        if let Some(outcome) = game.check_game_over() {
             self.status = GameStatus::Finished {
-                winner: Some(winner.clone()),
+                winner: outcome.winner().cloned(),
             };
-            println!("Game Over! Winner: {}", winner.0);
+            println!("Game Over! {:?}", outcome);
 
-            let msg = format!(
-                r#"{{"type":"game_over","payload":{{"winner":"{}"}}}}"#,
-                winner.0
-            );
+            let outcome_json = serde_json::to_string(&outcome).unwrap();
+            let msg = format!(r#"{{"type":"game_over","payload":{}}}"#, outcome_json);
             let _ = self.event_tx.send(msg);
         }
 
         // sendin the board
         self.broadcast_state();
-        Ok(())
     }
 
     fn get_snapshot(&self) -> GameStateSnapShot {
@@ -200,7 +359,12 @@ impl GameLoop {
         }
     }
 
-    async fn handle_join(&mut self, player_name: String) -> Result<PlayerId, JoinError> {
+    async fn handle_join(
+        &mut self,
+        player_name: String,
+        setup: GameSetup,
+        outbox: mpsc::Sender<String>,
+    ) -> Result<PlayerId, JoinError> {
         if matches!(
             &self.status,
             GameStatus::InProgress | GameStatus::Finished { .. }
@@ -217,10 +381,17 @@ impl GameLoop {
             return Err(JoinError::NameTaken);
         }
 
+        // whoever opens the game picks the board
+        if self.players.is_empty() {
+            setup.validate()?;
+            self.setup = setup;
+        }
+
         let raw_id = Uuid::new_v4().to_string();
         let player_id = PlayerId(raw_id);
 
         self.players.push((player_name.clone(), player_id.clone()));
+        self.outboxes.push((player_id.clone(), outbox));
         println!(
             "Player '{}' joined. Total players: {}",
             player_name,
@@ -231,7 +402,20 @@ impl GameLoop {
             let p1_id = self.players[0].1.clone();
             let p2_id = self.players[1].1.clone();
 
-            self.game = Some(GameState::new(p1_id, p2_id));
+            let (width, height) = self.setup.size();
+            let mut game = GameState::new(p1_id, p2_id, width, height, self.setup.grid)
+                .expect("board size is checked on join");
+            game.set_topology(self.setup.topology);
+            game.fog_of_war = self.setup.fog_of_war;
+            game.drops = self.setup.drops;
+            if let Some(points) = self.setup.spawn_points {
+                game.spawn_points = (points, points);
+            }
+            for (square, terrain) in &self.setup.terrain {
+                game.set_terrain(*square, *terrain)
+                    .expect("terrain is checked on join");
+            }
+            self.game = Some(game);
 
             self.status = GameStatus::InProgress;
 
@@ -250,6 +434,16 @@ impl GameLoop {
 
     fn broadcast_state(&self) {
         if let Some(game) = &self.game {
+            // under fog of war each player gets their own view, nobody sees the whole board
+            if game.fog_of_war {
+                for (player_id, outbox) in &self.outboxes {
+                    let view_json = serde_json::to_string(&game.view_for(player_id)).unwrap();
+                    let msg = format!(r#"{{"type":"state","payload":{}}}"#, view_json);
+                    let _ = outbox.try_send(msg);
+                }
+                return;
+            }
+
             let state_json = serde_json::to_string(game).unwrap();
             let msg = format!(r#"{{"type":"state","payload":{}}}"#, state_json);
             println!("Broadcasting state: {}", msg);
@@ -263,6 +457,7 @@ impl GameLoop {
         rule: core::rules::PieceRule,
     ) -> Result<(), GameError> {
         let game = self.game.as_mut().ok_or(GameError::GameNotStarted)?;
+        rule.validate(game.grid)?;
 
         if game.disagreement_count >= game.max_disagreements {
             println!("Max disagree reached force accepting rule");
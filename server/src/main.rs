@@ -13,7 +13,7 @@ use serde::Serialize;
 use tokio::sync::{broadcast, mpsc, oneshot};
 
 mod game_loop;
-use game_loop::{Command, GameLoop};
+use game_loop::{Command, GameLoop, GameSetup};
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type", content = "payload")]
@@ -21,11 +21,27 @@ pub enum ClientMessage {
     #[serde(rename = "join")]
     Join {
         name: String,
+        /// only the first player to join gets to set the game up
+        #[serde(flatten)]
+        setup: GameSetup,
     },
     #[serde(rename = "move")]
     Move {
         from: (u8, u8),
         to: (u8, u8),
+        /// rule name to promote into when landing in a promotion zone
+        #[serde(default)]
+        promotion: Option<String>,
+    },
+    #[serde(rename = "shoot")]
+    Shoot {
+        from: (u8, u8),
+        target: (u8, u8),
+    },
+    #[serde(rename = "drop")]
+    Drop {
+        piece: String,
+        at: (u8, u8),
     },
     #[serde(rename = "propose_rule")]
     ProposeRule {
@@ -122,10 +138,12 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
             if let Message::Text(text) = msg {
                 match serde_json::from_str::<ClientMessage>(&text) {
                     Ok(client_msg) => match client_msg {
-                        ClientMessage::Join { name } => {
+                        ClientMessage::Join { name, setup } => {
                             let (resp_tx, resp_rx) = oneshot::channel();
                             let cmd = Command::Join {
                                 player_name: name.clone(),
+                                setup,
+                                outbox: individual_tx.clone(),
                                 response: resp_tx,
                             };
 
@@ -154,7 +172,11 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
                                 }
                             }
                         }
-                        ClientMessage::Move { from, to } => {
+                        ClientMessage::Move {
+                            from,
+                            to,
+                            promotion,
+                        } => {
                             println!("Processing Move request: {:?} -> {:?}", from, to);
                             if let Some(pid) = &player_id {
                                 let (resp_tx, resp_rx) = oneshot::channel();
@@ -162,6 +184,7 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
                                     player_id: pid.clone(),
                                     from,
                                     to,
+                                    promotion,
                                     response: resp_tx,
                                 };
 
@@ -185,6 +208,54 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
                                 println!("Move request without joining first");
                             }
                         }
+                        ClientMessage::Shoot { from, target } => {
+                            if let Some(pid) = &player_id {
+                                let (resp_tx, resp_rx) = oneshot::channel();
+
+                                let cmd = Command::Shoot {
+                                    player_id: pid.clone(),
+                                    from,
+                                    target,
+                                    response: resp_tx,
+                                };
+
+                                if tx.send(cmd).await.is_err() {
+                                    break;
+                                }
+
+                                match resp_rx.await {
+                                    Ok(Ok(())) => println!("Shot: {:?} -> {:?}", from, target),
+                                    Ok(Err(e)) => println!("Shot error: {:?}", e),
+                                    Err(_) => println!("Channel closed"),
+                                }
+                            } else {
+                                println!("Shot request without joining first");
+                            }
+                        }
+                        ClientMessage::Drop { piece, at } => {
+                            if let Some(pid) = &player_id {
+                                let (resp_tx, resp_rx) = oneshot::channel();
+
+                                let cmd = Command::Drop {
+                                    player_id: pid.clone(),
+                                    piece_type: piece.clone(),
+                                    at,
+                                    response: resp_tx,
+                                };
+
+                                if tx.send(cmd).await.is_err() {
+                                    break;
+                                }
+
+                                match resp_rx.await {
+                                    Ok(Ok(())) => println!("Drop: {} on {:?}", piece, at),
+                                    Ok(Err(e)) => println!("Drop error: {:?}", e),
+                                    Err(_) => println!("Channel closed"),
+                                }
+                            } else {
+                                println!("Drop request without joining first");
+                            }
+                        }
                         ClientMessage::ProposeRule { rule } => {
                             if let Some(pid) = &player_id {
                                 let (resp_tx, resp_rx) = oneshot::channel();